@@ -0,0 +1,158 @@
+#![forbid(unsafe_code)]
+
+use std::time::Duration;
+
+use crossterm::event::{poll, read, Event, KeyCode};
+
+use crate::{output, theme::Palette};
+
+///Marks of the chart's lines, told apart even without color
+const MARKS: [char; 7] = ['*', '+', 'o', 'x', '#', '@', '%'];
+
+///Mark of lines overlaid on a series, like moving averages, drawn in the series' color
+const OVERLAY: char = '.';
+
+///Line of values at Unix timestamps
+pub type Series = Vec<(u64, f64)>;
+
+///Draws every series on one chart, with overlays under the series they belong to, above a legend with a row per series then per overlay, until q is pressed
+pub fn run(
+    series: &[Series],
+    overlays: &[(usize, Series)],
+    header: &str,
+    legend: &[String],
+    palette: &Palette,
+) {
+    output::setup();
+    let mut last_size = (0, 0);
+    loop {
+        let size = output::size();
+        if size != last_size {
+            last_size = size;
+            output::clear();
+            draw(series, overlays, header, legend, palette, size);
+        }
+        if poll(Duration::from_millis(250)).expect("Terminal error.")
+            && read().expect("Terminal error.") == Event::Key(KeyCode::Char('q').into())
+        {
+            break;
+        }
+    }
+}
+
+///Draws chart and legend sized to the terminal
+fn draw(
+    series: &[Series],
+    overlays: &[(usize, Series)],
+    header: &str,
+    legend: &[String],
+    palette: &Palette,
+    size: (u16, u16),
+) {
+    //Some terminals report no size, a common one is assumed then
+    let (width, height) = match size {
+        (0, _) | (_, 0) => (80, 24),
+        (width, height) => (width as usize, height as usize),
+    };
+    let label = 8;
+    let columns = width.saturating_sub(label + 1).max(10);
+    //Keeps rows for the legend, its header and the footer
+    let rows = height.saturating_sub(legend.len() + 4).max(5);
+    let points = series
+        .iter()
+        .chain(overlays.iter().map(|(_, overlay)| overlay))
+        .flatten();
+    let (start, end) = points
+        .clone()
+        .fold((u64::MAX, 0), |(start, end), (time, _)| {
+            (start.min(*time), end.max(*time))
+        });
+    let (low, high) = points.fold((f64::MAX, f64::MIN), |(low, high), (_, value)| {
+        (low.min(*value), high.max(*value))
+    });
+    let (low, high) = if high > low {
+        (low, high)
+    } else {
+        (low - 1.0, high + 1.0)
+    };
+    let x = |time: u64| {
+        ((time - start) as f64 / (end - start).max(1) as f64 * (columns - 1) as f64).round()
+            as usize
+    };
+    let y =
+        |value: f64| rows - 1 - ((value - low) / (high - low) * (rows - 1) as f64).round() as usize;
+    //Cells hold the series drawn there and whether it is an overlay of it
+    let mut grid: Vec<Vec<Option<(usize, bool)>>> = vec![vec![None; columns]; rows];
+    //Overlays go first so the series themselves stay on top
+    let lines = overlays
+        .iter()
+        .map(|(index, overlay)| (*index, true, overlay))
+        .chain(
+            series
+                .iter()
+                .enumerate()
+                .map(|(index, series)| (index, false, series)),
+        );
+    for (index, overlay, series) in lines {
+        let mut previous: Option<usize> = None;
+        for (time, value) in series {
+            let (column, row) = (x(*time), y(*value));
+            //Fills the rows jumped since the previous point so lines stay joined
+            let (top, bottom) = match previous {
+                Some(previous) => (previous.min(row), previous.max(row)),
+                None => (row, row),
+            };
+            for cells in &mut grid[top..=bottom] {
+                cells[column] = Some((index, overlay));
+            }
+            previous = Some(row);
+        }
+    }
+    let baseline = (low..=high).contains(&100.0).then(|| y(100.0));
+    for (row, cells) in grid.iter().enumerate() {
+        output::move_to(0, row as u16);
+        let value = low + (high - low) * (rows - 1 - row) as f64 / (rows - 1) as f64;
+        let text = if row == 0 || row == rows - 1 || Some(row) == baseline {
+            format!("{:>7.1} ", value)
+        } else {
+            " ".repeat(label)
+        };
+        output::write(&text);
+        output::write("|");
+        for cell in cells {
+            match cell {
+                Some((index, overlay)) => {
+                    output::set_color(palette.series(*index));
+                    output::write(&mark(*index, *overlay).to_string());
+                    output::reset_color();
+                }
+                None if Some(row) == baseline => output::write("-"),
+                None => output::write(" "),
+            }
+        }
+    }
+    output::move_to(0, rows as u16 + 1);
+    output::set_color(palette.header);
+    output::write_then_nextline(&format!("   {}", header));
+    output::reset_color();
+    let owners = (0..series.len())
+        .map(|index| (index, false))
+        .chain(overlays.iter().map(|(index, _)| (*index, true)));
+    for (line, (index, overlay)) in legend.iter().zip(owners) {
+        output::set_color(palette.series(index));
+        output::write(&format!(" {} ", mark(index, overlay)));
+        output::reset_color();
+        output::write_then_nextline(line);
+    }
+    output::skip_line();
+    output::write("Press q to quit.");
+}
+
+///Returns mark of a series or of its overlays
+fn mark(index: usize, overlay: bool) -> char {
+    if overlay {
+        OVERLAY
+    } else {
+        MARKS[index % MARKS.len()]
+    }
+}
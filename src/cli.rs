@@ -0,0 +1,721 @@
+#![forbid(unsafe_code)]
+
+///Kind of value an option takes
+#[derive(Clone, Copy, PartialEq)]
+pub enum Kind {
+    Switch,
+    Text(&'static str),
+    Choice(&'static [&'static str]),
+    Number,
+}
+
+///Option accepted on the command line
+pub struct Flag {
+    pub long: &'static str,
+    pub short: Option<&'static str>,
+    pub kind: Kind,
+    pub help: &'static str,
+}
+
+///Subcommand with its arguments written as usage, <required>, [optional], ... for many and bare words as keywords
+pub struct Command {
+    pub name: &'static str,
+    pub usage: &'static str,
+    pub flags: &'static [Flag],
+    pub help: &'static str,
+}
+
+const fn switch(long: &'static str, help: &'static str) -> Flag {
+    Flag {
+        long,
+        short: None,
+        kind: Kind::Switch,
+        help,
+    }
+}
+
+const fn value(long: &'static str, kind: Kind, help: &'static str) -> Flag {
+    Flag {
+        long,
+        short: None,
+        kind,
+        help,
+    }
+}
+
+///Exit status when the command line cannot be used, apart from declined prompts and partial results
+pub const USAGE: i32 = 64;
+
+pub const INTERVALS: &[&str] = &["1d", "1mo", "1y"];
+
+pub const RANGES: &[&str] = &[
+    "1d", "5d", "1mo", "3mo", "6mo", "1y", "2y", "5y", "10y", "ytd", "max",
+];
+
+pub const BARS: &[&str] = &["1h", "1d", "1wk", "1mo"];
+
+pub const TRANSFER_FORMATS: &[&str] = &["json", "toml", "csv", "txt"];
+
+///Options every command accepts
+pub const GLOBAL: &[Flag] = &[
+    value(
+        "--config",
+        Kind::Text("file"),
+        "Uses another configuration file",
+    ),
+    value(
+        "--profile",
+        Kind::Text("name"),
+        "Uses the configuration of a profile",
+    ),
+    Flag {
+        long: "--yes",
+        short: Some("-y"),
+        kind: Kind::Switch,
+        help: "Answers yes to prompts",
+    },
+    switch("--no", "Answers no to prompts"),
+    Flag {
+        long: "--quiet",
+        short: Some("-q"),
+        kind: Kind::Switch,
+        help: "Prints nothing but errors",
+    },
+    Flag {
+        long: "--help",
+        short: Some("-h"),
+        kind: Kind::Switch,
+        help: "Shows usage",
+    },
+    Flag {
+        long: "--version",
+        short: Some("-V"),
+        kind: Kind::Switch,
+        help: "Shows version",
+    },
+];
+
+const SHOW: &[Flag] = &[
+    value(
+        "--interval",
+        Kind::Choice(INTERVALS),
+        "Period the change covers",
+    ),
+    value(
+        "--format",
+        Kind::Choice(&["table", "plain", "heatmap", "text", "json", "csv"]),
+        "How prices are shown",
+    ),
+    switch("--plain", "Same as --format plain"),
+    switch("--heatmap", "Same as --format heatmap"),
+    switch("--json", "Same as --format json"),
+    value(
+        "--sort",
+        Kind::Choice(&["symbol", "change"]),
+        "Orders rows without changing the watchlist",
+    ),
+    value("--watch", Kind::Number, "Refreshes every provided seconds"),
+    value(
+        "--columns",
+        Kind::Text("list"),
+        "Adds indicator columns like rsi14,sma50,ema20,macd,bbupper20,atr14",
+    ),
+];
+
+const FROM_FILE: &[Flag] = &[value(
+    "--from-file",
+    Kind::Text("file"),
+    "Reads tickers from a file, one per line or comma separated",
+)];
+
+///Every subcommand, the first one runs when none is provided and ones starting with __ are hidden
+pub const COMMANDS: &[Command] = &[
+    Command {
+        name: "show",
+        usage: "[watchlist] [interval]",
+        flags: SHOW,
+        help: "Shows a watchlist, \"default\" if none is provided",
+    },
+    Command {
+        name: "quote",
+        usage: "<tickers>...",
+        flags: SHOW,
+        help: "Shows tickers without saving them to a watchlist",
+    },
+    Command {
+        name: "search",
+        usage: "<query>...",
+        flags: &[
+            value(
+                "--format",
+                Kind::Choice(&["table", "text", "json", "csv"]),
+                "How results are shown",
+            ),
+            switch("--json", "Same as --format json"),
+            value(
+                "--type",
+                Kind::Choice(&[
+                    "equity",
+                    "etf",
+                    "crypto",
+                    "future",
+                    "currency",
+                    "index",
+                    "mutualfund",
+                ]),
+                "Only shows results of a quote type",
+            ),
+            value(
+                "--exchange",
+                Kind::Text("code"),
+                "Only shows results from an exchange, like NMS or CCC",
+            ),
+        ],
+        help: "Searches tickers by name",
+    },
+    Command {
+        name: "performance",
+        usage: "[watchlist]",
+        flags: &[
+            value(
+                "--format",
+                Kind::Choice(&["table", "text", "json", "csv"]),
+                "How returns are shown",
+            ),
+            switch("--json", "Same as --format json"),
+        ],
+        help: "Shows 1D, 1W, 1M, 3M, YTD, 1Y and 5Y returns of a watchlist",
+    },
+    Command {
+        name: "compare",
+        usage: "<tickers>...",
+        flags: &[
+            value(
+                "--range",
+                Kind::Choice(RANGES),
+                "Period compared, 1y if not provided",
+            ),
+            value(
+                "--format",
+                Kind::Choice(&["chart", "text", "json", "csv"]),
+                "How the comparison is shown",
+            ),
+            switch("--json", "Same as --format json"),
+            value(
+                "--columns",
+                Kind::Text("list"),
+                "Draws sma, ema, bbupper or bblower over every ticker, periods count chart bars",
+            ),
+        ],
+        help:
+            "Charts tickers together starting from 100 with their return, volatility and drawdown",
+    },
+    Command {
+        name: "risk",
+        usage: "[watchlist]",
+        flags: &[
+            value(
+                "--benchmark",
+                Kind::Text("ticker"),
+                "Ticker beta is measured against, \"benchmark\" in the configuration if not provided",
+            ),
+            value(
+                "--range",
+                Kind::Choice(RANGES),
+                "Period of daily history used, 1y if not provided",
+            ),
+            value(
+                "--format",
+                Kind::Choice(&["table", "json", "csv"]),
+                "How statistics are shown",
+            ),
+            switch("--json", "Same as --format json"),
+        ],
+        help: "Prints volatility, max drawdown, Sharpe ratio and beta of a watchlist's tickers",
+    },
+    Command {
+        name: "correlation",
+        usage: "[watchlist]",
+        flags: &[
+            value(
+                "--range",
+                Kind::Choice(RANGES),
+                "Period of daily history used, 1y if not provided",
+            ),
+            value(
+                "--format",
+                Kind::Choice(&["table", "json", "csv"]),
+                "How the matrix is shown",
+            ),
+            switch("--json", "Same as --format json"),
+        ],
+        help: "Prints correlation of daily returns between a watchlist's tickers",
+    },
+    Command {
+        name: "history",
+        usage: "<ticker>",
+        flags: &[
+            value(
+                "--range",
+                Kind::Choice(RANGES),
+                "Period the history covers, 1y if not provided",
+            ),
+            value(
+                "--bar",
+                Kind::Choice(BARS),
+                "Period of every row, 1d if not provided",
+            ),
+            value(
+                "--format",
+                Kind::Choice(&["table", "csv", "json"]),
+                "How rows are shown",
+            ),
+            switch("--json", "Same as --format json"),
+        ],
+        help: "Prints open, high, low, close, adjusted close and volume of a ticker",
+    },
+    Command {
+        name: "new",
+        usage: "<watchlist> [tickers]...",
+        flags: FROM_FILE,
+        help: "Creates a watchlist, - reads tickers from stdin",
+    },
+    Command {
+        name: "add",
+        usage: "<watchlist> [tickers]...",
+        flags: FROM_FILE,
+        help: "Adds tickers to a watchlist, - reads tickers from stdin",
+    },
+    Command {
+        name: "remove",
+        usage: "<watchlist> [tickers]...",
+        flags: &[],
+        help: "Removes tickers or patterns like '*-USD' from a watchlist",
+    },
+    Command {
+        name: "delete",
+        usage: "<watchlist>",
+        flags: &[],
+        help: "Deletes a watchlist",
+    },
+    Command {
+        name: "rename",
+        usage: "<watchlist> <name>",
+        flags: &[],
+        help: "Renames a watchlist",
+    },
+    Command {
+        name: "copy",
+        usage: "<watchlist> <name>",
+        flags: &[],
+        help: "Copies a watchlist",
+    },
+    Command {
+        name: "merge",
+        usage: "<watchlist> <other> into <name>",
+        flags: &[],
+        help: "Combines two watchlists",
+    },
+    Command {
+        name: "move",
+        usage: "<watchlist> <ticker> <position>",
+        flags: &[],
+        help: "Moves a ticker to a position starting from 1",
+    },
+    Command {
+        name: "sort",
+        usage: "<watchlist>",
+        flags: &[value(
+            "--by",
+            Kind::Choice(&["symbol", "change"]),
+            "Sorts by ticker symbol or by the day's change",
+        )],
+        help: "Sorts a watchlist",
+    },
+    Command {
+        name: "list",
+        usage: "",
+        flags: &[],
+        help: "Lists saved watchlists",
+    },
+    Command {
+        name: "colorswap",
+        usage: "",
+        flags: &[],
+        help: "Swaps green and red for some East Asian users",
+    },
+    Command {
+        name: "theme",
+        usage: "[name]",
+        flags: &[],
+        help: "Lists themes or switches to one",
+    },
+    Command {
+        name: "config",
+        usage: "<action> [key] [value]",
+        flags: &[],
+        help: "Runs path, get <key>, set <key> <value>, edit or validate",
+    },
+    Command {
+        name: "export",
+        usage: "[watchlists]...",
+        flags: &[value(
+            "--format",
+            Kind::Choice(TRANSFER_FORMATS),
+            "File format, json if not provided",
+        )],
+        help: "Prints watchlists for moving them between machines",
+    },
+    Command {
+        name: "import",
+        usage: "<file>",
+        flags: &[
+            value(
+                "--format",
+                Kind::Choice(TRANSFER_FORMATS),
+                "File format, guessed from the extension if not provided",
+            ),
+            switch("--merge", "Adds tickers to watchlists that already exist"),
+            switch("--replace", "Overwrites watchlists that already exist"),
+        ],
+        help: "Imports watchlists written by export",
+    },
+    Command {
+        name: "undo",
+        usage: "",
+        flags: &[],
+        help: "Reverts the last change to watchlists or settings",
+    },
+    Command {
+        name: "backups",
+        usage: "[action] [number]",
+        flags: &[],
+        help: "Runs list or restore <number>",
+    },
+    Command {
+        name: "completions",
+        usage: "<shell>",
+        flags: &[],
+        help: "Prints completion script for bash, zsh or fish",
+    },
+    Command {
+        name: "__complete",
+        usage: "[words]...",
+        flags: &[],
+        help: "Prints candidates for completion scripts",
+    },
+    Command {
+        name: "help",
+        usage: "",
+        flags: &[],
+        help: "Shows list of commands",
+    },
+];
+
+///Command line broken down by the command's usage and flags
+pub struct Parsed {
+    pub command: &'static Command,
+    arguments: Vec<(&'static str, Vec<String>)>,
+    values: Vec<(&'static str, String)>,
+}
+
+impl Parsed {
+    ///Returns first value of a positional argument
+    pub fn argument(&self, name: &str) -> Option<&str> {
+        self.arguments(name).first().map(|value| value.as_str())
+    }
+
+    ///Returns all values of a positional argument
+    pub fn arguments(&self, name: &str) -> &[String] {
+        self.arguments
+            .iter()
+            .find(|(existing, _)| *existing == name)
+            .map_or(&[], |(_, values)| values.as_slice())
+    }
+
+    ///Returns value of an option, last one wins
+    pub fn value(&self, long: &str) -> Option<&str> {
+        self.values
+            .iter()
+            .rev()
+            .find(|(existing, _)| *existing == long)
+            .map(|(_, value)| value.as_str())
+    }
+
+    ///Checks whether an option was provided
+    pub fn has(&self, long: &str) -> bool {
+        self.values.iter().any(|(existing, _)| *existing == long)
+    }
+}
+
+///Finds a subcommand by name
+pub fn command(name: &str) -> Option<&'static Command> {
+    COMMANDS.iter().find(|command| command.name == name)
+}
+
+///Parses arguments without the program name, errors are usage messages
+pub fn parse(arguments: &[String]) -> Result<Parsed, String> {
+    let mut positionals: Vec<String> = vec![];
+    let mut values: Vec<(&'static str, String)> = vec![];
+    let mut command: Option<&'static Command> = None;
+    let mut index = 0;
+    let mut options_ended = false;
+    while index < arguments.len() {
+        let argument = &arguments[index];
+        index += 1;
+        //Negative numbers like -0.5 are values, not options
+        if options_ended
+            || argument == "-"
+            || !argument.starts_with('-')
+            || argument.parse::<f64>().is_ok()
+        {
+            if command.is_none() && positionals.is_empty() {
+                match self::command(argument) {
+                    Some(found) => {
+                        command = Some(found);
+                        continue;
+                    }
+                    None => return Err(format!("Unknown command \"{}\".", argument)),
+                }
+            }
+            positionals.push(argument.clone());
+            continue;
+        }
+        if argument == "--" {
+            options_ended = true;
+            continue;
+        }
+        let (name, inline) = match argument.split_once('=') {
+            Some((name, inline)) if name.starts_with("--") => (name, Some(inline.to_string())),
+            _ => (argument.as_str(), None),
+        };
+        let current = command.unwrap_or(&COMMANDS[0]);
+        let flag = GLOBAL
+            .iter()
+            .chain(current.flags.iter())
+            .find(|flag| flag.long == name || flag.short == Some(name))
+            .ok_or_else(|| format!("Unknown option {} for {}.", name, current.name))?;
+        let value = match flag.kind {
+            Kind::Switch if inline.is_some() => {
+                return Err(format!("{} does not take a value.", flag.long))
+            }
+            Kind::Switch => String::new(),
+            _ => match inline {
+                Some(value) => value,
+                None if index < arguments.len() => {
+                    index += 1;
+                    arguments[index - 1].clone()
+                }
+                None => return Err(format!("{} needs a value.", flag.long)),
+            },
+        };
+        check(flag, &value)?;
+        values.push((flag.long, value));
+    }
+    let command = command.unwrap_or(&COMMANDS[0]);
+    if values
+        .iter()
+        .any(|(long, _)| *long == "--help" || *long == "--version")
+    {
+        return Ok(Parsed {
+            command,
+            arguments: vec![],
+            values,
+        });
+    }
+    let arguments = bind(command, positionals)?;
+    Ok(Parsed {
+        command,
+        arguments,
+        values,
+    })
+}
+
+///Checks an option's value against its kind
+fn check(flag: &Flag, value: &str) -> Result<(), String> {
+    match flag.kind {
+        Kind::Choice(choices) if !choices.contains(&value) => Err(format!(
+            "Invalid value \"{}\" for {}. Use {}.",
+            value,
+            flag.long,
+            choices.join(", ")
+        )),
+        Kind::Number if value.parse::<u64>().is_err() => Err(format!(
+            "Invalid value \"{}\" for {}. Use a whole number.",
+            value, flag.long
+        )),
+        _ => Ok(()),
+    }
+}
+
+///Assigns positional values to the names in the command's usage
+fn bind(
+    command: &Command,
+    positionals: Vec<String>,
+) -> Result<Vec<(&'static str, Vec<String>)>, String> {
+    let mut bound = vec![];
+    let mut remaining = positionals.into_iter().peekable();
+    for word in command.usage.split_whitespace() {
+        let many = word.ends_with("...");
+        let word = word.trim_end_matches("...");
+        if let Some(name) = word
+            .strip_prefix('<')
+            .and_then(|rest| rest.strip_suffix('>'))
+        {
+            let values: Vec<String> = if many {
+                remaining.by_ref().collect()
+            } else {
+                remaining.next().into_iter().collect()
+            };
+            if values.is_empty() {
+                return Err(format!("Missing <{}> for {}.", name, command.name));
+            }
+            bound.push((name, values));
+        } else if let Some(name) = word
+            .strip_prefix('[')
+            .and_then(|rest| rest.strip_suffix(']'))
+        {
+            let values: Vec<String> = if many {
+                remaining.by_ref().collect()
+            } else {
+                remaining.next().into_iter().collect()
+            };
+            bound.push((name, values));
+        } else if remaining.next().as_deref() != Some(word) {
+            return Err(format!("Expected \"{}\" for {}.", word, command.name));
+        }
+    }
+    match remaining.next() {
+        Some(extra) => Err(format!(
+            "Unexpected argument \"{}\" for {}.",
+            extra, command.name
+        )),
+        None => Ok(bound),
+    }
+}
+
+///Writes usage of a command with its options
+pub fn usage(command: &Command) -> String {
+    let mut text = format!(
+        "{}\n\nUsage: zigfi {} {}",
+        command.help, command.name, command.usage
+    );
+    text = text.trim_end().to_string();
+    text.push_str(" [options]\n\nOptions:\n");
+    let names: Vec<(String, &str)> = command
+        .flags
+        .iter()
+        .chain(GLOBAL.iter())
+        .map(|flag| {
+            let mut name = match flag.short {
+                Some(short) => format!("{}, {}", short, flag.long),
+                None => flag.long.to_string(),
+            };
+            match flag.kind {
+                Kind::Switch => {}
+                Kind::Text(value) => name.push_str(&format!(" <{}>", value)),
+                Kind::Choice(choices) => name.push_str(&format!(" <{}>", choices.join("|"))),
+                Kind::Number => name.push_str(" <number>"),
+            }
+            (name, flag.help)
+        })
+        .collect();
+    let width = names.iter().map(|(name, _)| name.len()).max().unwrap_or(0) + 2;
+    for (name, help) in names {
+        text.push_str(&format!("  {:<width$}{}\n", name, help, width = width));
+    }
+    text
+}
+
+///Writes list of commands
+pub fn overview() -> String {
+    let mut text =
+        String::from("zigfi - stocks, commodities and cryptocurrencies prices\n\nCommands:\n");
+    for command in COMMANDS
+        .iter()
+        .filter(|command| !command.name.starts_with("__"))
+    {
+        text.push_str(&format!("  {:<12}{}\n", command.name, command.help));
+    }
+    text.push_str("\nRun zigfi <command> --help for its usage and options.\n");
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(line: &str) -> Result<Parsed, String> {
+        let arguments: Vec<String> = line.split_whitespace().map(String::from).collect();
+        parse(&arguments)
+    }
+
+    #[test]
+    fn no_command_runs_show() {
+        let parsed = run("").expect("Rejected");
+        assert_eq!(parsed.command.name, "show");
+        assert_eq!(parsed.argument("watchlist"), None);
+    }
+
+    #[test]
+    fn options_take_values_inline_or_after() {
+        let parsed = run("show crypto --interval=1mo --watch 5 --json").expect("Rejected");
+        assert_eq!(parsed.argument("watchlist"), Some("crypto"));
+        assert_eq!(parsed.value("--interval"), Some("1mo"));
+        assert_eq!(parsed.value("--watch"), Some("5"));
+        assert!(parsed.has("--json"));
+        assert!(!parsed.has("--plain"));
+    }
+
+    #[test]
+    fn option_errors_are_reported() {
+        assert!(run("show --bogus").is_err());
+        assert!(run("show --interval 2d").is_err());
+        assert!(run("show --watch soon").is_err());
+        assert!(run("show --json=yes").is_err());
+        assert!(run("show --interval").is_err());
+        assert!(run("bogus").is_err());
+    }
+
+    #[test]
+    fn negative_numbers_are_values() {
+        let parsed = run("config set risk_free_rate -0.5").expect("Rejected");
+        assert_eq!(parsed.argument("action"), Some("set"));
+        assert_eq!(parsed.argument("key"), Some("risk_free_rate"));
+        assert_eq!(parsed.argument("value"), Some("-0.5"));
+    }
+
+    #[test]
+    fn double_dash_ends_options() {
+        let parsed = run("__complete -- show --json").expect("Rejected");
+        assert_eq!(parsed.arguments("words"), ["show", "--json"]);
+    }
+
+    #[test]
+    fn bind_fills_required_optional_and_many() {
+        let parsed = run("add crypto BTC-USD ETH-USD").expect("Rejected");
+        assert_eq!(parsed.argument("watchlist"), Some("crypto"));
+        assert_eq!(parsed.arguments("tickers"), ["BTC-USD", "ETH-USD"]);
+        let parsed = run("new empty").expect("Rejected");
+        assert!(parsed.arguments("tickers").is_empty());
+        assert!(run("quote").is_err());
+        assert!(run("rename crypto").is_err());
+        assert!(run("delete crypto extra").is_err());
+    }
+
+    #[test]
+    fn bind_expects_keywords() {
+        let parsed = run("merge a b into c").expect("Rejected");
+        assert_eq!(parsed.argument("watchlist"), Some("a"));
+        assert_eq!(parsed.argument("other"), Some("b"));
+        assert_eq!(parsed.argument("name"), Some("c"));
+        assert!(run("merge a b onto c").is_err());
+    }
+
+    #[test]
+    fn help_skips_argument_checks() {
+        let parsed = run("rename --help").expect("Rejected");
+        assert_eq!(parsed.command.name, "rename");
+        assert!(parsed.has("--help"));
+    }
+}
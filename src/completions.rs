@@ -0,0 +1,245 @@
+#![forbid(unsafe_code)]
+
+use crate::cli::{self, Command, Kind};
+use crate::config::{self, Config};
+
+///Shells completion scripts can be written for
+pub const SHELLS: &[&str] = &["bash", "zsh", "fish"];
+
+const BASH: &str = r#"_zigfi() {
+    local line="${COMP_LINE:0:COMP_POINT}"
+    local -a words
+    IFS=' ' read -r -a words <<< "$line"
+    [[ "$line" == *" " ]] && words+=("")
+    local typed="${words[-1]}"
+    local prefix="${typed%"${COMP_WORDS[COMP_CWORD]}"}"
+    local IFS=$'\n'
+    COMPREPLY=($(zigfi __complete -- "${words[@]:1}" 2>/dev/null))
+    COMPREPLY=("${COMPREPLY[@]#"$prefix"}")
+}
+complete -o default -F _zigfi zigfi
+"#;
+
+const ZSH: &str = r#"#compdef zigfi
+_zigfi() {
+    local -a candidates
+    candidates=("${(@f)$(zigfi __complete -- "${(@)words[2,CURRENT]}" 2>/dev/null)}")
+    if [[ -n "${candidates[1]}" ]]; then
+        compadd -- "${candidates[@]}"
+    else
+        _files
+    fi
+}
+if [[ "${funcstack[1]}" == "_zigfi" ]]; then
+    _zigfi "$@"
+else
+    compdef _zigfi zigfi
+fi
+"#;
+
+const FISH: &str = r#"function __zigfi_complete
+    set -l candidates (zigfi __complete -- (commandline -opc)[2..-1] (commandline -ct) 2>/dev/null)
+    if test (count $candidates) -gt 0
+        printf '%s\n' $candidates
+    else
+        __fish_complete_path (commandline -ct)
+    end
+end
+complete -c zigfi -f -a '(__zigfi_complete)'
+"#;
+
+///Returns completion script for a shell, which asks zigfi for candidates as the user types
+pub fn script(shell: &str) -> Option<&'static str> {
+    match shell {
+        "bash" => Some(BASH),
+        "zsh" => Some(ZSH),
+        "fish" => Some(FISH),
+        _ => None,
+    }
+}
+
+///Returns candidates for the last of the words typed after zigfi
+pub fn candidates(words: &[String]) -> Vec<String> {
+    suggest(words, &load)
+}
+
+///Returns candidates, reading the configuration chosen by --config and --profile with load
+fn suggest(
+    words: &[String],
+    load: &dyn Fn(Option<String>, Option<String>) -> Config,
+) -> Vec<String> {
+    let (current, before) = match words.split_last() {
+        Some((current, before)) => (current.as_str(), before),
+        None => ("", words),
+    };
+    let mut command: Option<&'static Command> = None;
+    let mut positionals: Vec<&str> = vec![];
+    let mut pending: Option<(&str, Kind)> = None;
+    //Watchlists and themes come from the configuration the typed command would use
+    let (mut file, mut profile) = (None, None);
+    for word in before {
+        if let Some((name, kind)) = pending.take() {
+            if kind != Kind::Switch {
+                match name {
+                    "--config" => file = Some(word.clone()),
+                    "--profile" if config::valid_profile(word) => profile = Some(word.clone()),
+                    _ => {}
+                }
+                continue;
+            }
+        }
+        if word.starts_with('-') && word != "-" {
+            pending = flag(command, word).map(|flag| (flag.long, flag.kind));
+        } else if command.is_none() && positionals.is_empty() {
+            command = cli::command(word);
+        } else {
+            positionals.push(word);
+        }
+    }
+    let mut candidates: Vec<String> = match pending.map(|(_, kind)| kind) {
+        Some(Kind::Choice(choices)) => choices.iter().map(|choice| choice.to_string()).collect(),
+        Some(Kind::Text(_)) | Some(Kind::Number) => vec![],
+        _ if current.starts_with('-') => command
+            .unwrap_or(&cli::COMMANDS[0])
+            .flags
+            .iter()
+            .chain(cli::GLOBAL.iter())
+            .flat_map(|flag| flag.short.into_iter().chain([flag.long]))
+            .map(|name| name.to_string())
+            .collect(),
+        _ => match command {
+            None if positionals.is_empty() => cli::COMMANDS
+                .iter()
+                .filter(|command| !command.name.starts_with("__"))
+                .map(|command| command.name.to_string())
+                .collect(),
+            None => vec![],
+            Some(command) => arguments(command, &positionals, &load(file, profile)),
+        },
+    };
+    candidates.retain(|candidate| candidate.starts_with(current));
+    candidates
+}
+
+///Finds an option of the command, show if none was typed yet, or a global one
+fn flag(command: Option<&'static Command>, word: &str) -> Option<&'static cli::Flag> {
+    if word.contains('=') {
+        return None;
+    }
+    command
+        .unwrap_or(&cli::COMMANDS[0])
+        .flags
+        .iter()
+        .chain(cli::GLOBAL.iter())
+        .find(|flag| flag.long == word || flag.short == Some(word))
+}
+
+///Returns candidates for the next positional argument of a command
+fn arguments(command: &Command, positionals: &[&str], cfg: &Config) -> Vec<String> {
+    let name = command
+        .usage
+        .split_whitespace()
+        .enumerate()
+        .find(|(index, word)| *index == positionals.len() || word.ends_with("..."))
+        .map(|(_, word)| word.trim_matches(|c| "<>[].".contains(c)));
+    let watchlists = || {
+        let mut names: Vec<String> = cfg.watchlists.keys().cloned().collect();
+        names.sort();
+        names
+    };
+    match (command.name, name) {
+        (_, Some("watchlist")) | (_, Some("other")) | (_, Some("watchlists")) => watchlists(),
+        ("remove", Some("tickers")) | ("move", Some("ticker")) => positionals
+            .first()
+            .and_then(|watchlist| cfg.watchlists.get(*watchlist))
+            .cloned()
+            .unwrap_or_default(),
+        ("merge", Some("into")) => vec!["into".to_string()],
+        ("show", Some("interval")) => words(cli::INTERVALS),
+        ("theme", Some("name")) => {
+            let mut names = words(&crate::theme::BUILTIN);
+            names.extend(cfg.themes.keys().cloned());
+            names
+        }
+        ("config", Some("action")) => words(&["path", "get", "set", "edit", "validate"]),
+        ("config", Some("key")) => words(&config::KEYS),
+        ("backups", Some("action")) => words(&["list", "restore"]),
+        ("completions", Some("shell")) => words(SHELLS),
+        _ => vec![],
+    }
+}
+
+///Turns fixed words into candidates
+fn words(words: &[&str]) -> Vec<String> {
+    words.iter().map(|word| word.to_string()).collect()
+}
+
+///Reads configuration without creating, migrating or locking it, as completions run on every key press
+fn load(file: Option<String>, profile: Option<String>) -> Config {
+    std::fs::read_to_string(config::resolve(file, profile))
+        .ok()
+        .and_then(|text| config::parse(&text).ok())
+        .map(|(cfg, _)| cfg)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    ///Loads inline configurations instead of the user's file, the work profile and --config file have their own
+    fn load(file: Option<String>, profile: Option<String>) -> Config {
+        let text = match (file.as_deref(), profile.as_deref()) {
+            (Some("other.toml"), _) => "[watchlists]\nfile = [\"EURUSD=X\"]\n",
+            (None, Some("work")) => "[watchlists]\nwork = [\"MSFT\"]\n",
+            _ => "[watchlists]\ncrypto = [\"BTC-USD\", \"ETH-USD\"]\ndefault = [\"AAPL\"]\n\n[themes.mine]\nup = \"blue\"\n",
+        };
+        config::parse(text).expect("Invalid test configuration").0
+    }
+
+    fn complete(line: &str) -> Vec<String> {
+        let mut words: Vec<String> = line.split(' ').map(String::from).collect();
+        if line.is_empty() {
+            words = vec![String::new()];
+        }
+        suggest(&words, &load)
+    }
+
+    #[test]
+    fn completes_commands() {
+        let commands = complete("");
+        assert!(commands.contains(&"show".to_string()));
+        assert!(!commands.iter().any(|command| command.starts_with("__")));
+        assert_eq!(complete("re"), vec!["remove", "rename"]);
+    }
+
+    #[test]
+    fn completes_flags_and_their_values() {
+        assert_eq!(complete("show --int"), vec!["--interval"]);
+        assert_eq!(complete("show --interval "), vec!["1d", "1mo", "1y"]);
+        assert!(complete("show --watch ").is_empty());
+        assert!(complete("--pro").contains(&"--profile".to_string()));
+    }
+
+    #[test]
+    fn completes_watchlists_and_their_tickers() {
+        assert_eq!(complete("show "), vec!["crypto", "default"]);
+        assert_eq!(complete("remove crypto "), vec!["BTC-USD", "ETH-USD"]);
+        assert_eq!(complete("remove crypto BTC-USD E"), vec!["ETH-USD"]);
+        assert!(complete("remove missing ").is_empty());
+    }
+
+    #[test]
+    fn completes_themes_and_keywords() {
+        assert!(complete("theme ").contains(&"mine".to_string()));
+        assert_eq!(complete("merge crypto default "), vec!["into"]);
+        assert_eq!(complete("config set risk"), vec!["risk_free_rate"]);
+    }
+
+    #[test]
+    fn config_and_profile_choose_the_file() {
+        assert_eq!(complete("--profile work show "), vec!["work"]);
+        assert_eq!(complete("show --config other.toml "), vec!["file"]);
+        assert_eq!(complete("--profile ../x show "), vec!["crypto", "default"]);
+    }
+}
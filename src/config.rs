@@ -0,0 +1,632 @@
+#![forbid(unsafe_code)]
+
+use chrono::TimeZone;
+use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs,
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+    sync::OnceLock,
+    time::Duration,
+};
+
+use crate::{format, theme};
+
+///Current layout of the configuration file, bump and add a step to MIGRATIONS when it changes
+pub const VERSION: u32 = 3;
+
+///Steps upgrading a configuration from the version at their index plus one to the next
+const MIGRATIONS: [fn(&mut toml::value::Table); 2] = [from_v1, from_v2];
+
+///Number of backups kept for each configuration file
+const BACKUPS: usize = 20;
+
+///Keys of KEYS files older than version 3 could not have
+const SINCE_V3: [&str; 4] = ["provider", "refresh", "benchmark", "risk_free_rate"];
+
+///Providers zigfi can get prices from
+pub const PROVIDERS: [&str; 1] = ["yahoo"];
+
+///Configuration file chosen with --config or --profile, set once at startup
+static SELECTED: OnceLock<PathBuf> = OnceLock::new();
+
+///zigfi configuration structure, missing keys fall back to their defaults
+///
+///Tables have to come after plain values, TOML cannot write a value once a table has started
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct Config {
+    ///Layout version the file was written with
+    pub version: u32,
+    pub greenisup: bool,
+    ///Locale tag like "de_DE" for number and date formatting, detected from the environment if unset
+    pub locale: Option<String>,
+    ///Name of the active color theme
+    pub theme: String,
+    ///Direction indicator next to changes: "none", "arrows" or "signs"
+    pub indicator: String,
+    ///Shows gains in bold and losses dimmed so direction reads without color
+    pub emphasis: bool,
+    ///Displays watchlists as plain text without Alternate Screen or cursor movement
+    pub plain: bool,
+    ///Scales change colors with the size of the move instead of using a single up and down color
+    pub gradient: bool,
+    ///Move in percent at which gradient colors reach full strength
+    pub gradient_range: f64,
+    ///Where prices come from, overridable with ZIGFI_PROVIDER
+    pub provider: String,
+    ///Milliseconds to wait for a key between refreshes of live views, overridable with ZIGFI_REFRESH
+    pub refresh: u64,
+    ///Ticker risk statistics measure beta against
+    pub benchmark: String,
+    ///Yearly return in percent of a riskless asset, subtracted from returns for the Sharpe ratio
+    pub risk_free_rate: f64,
+    pub watchlists: HashMap<String, Vec<String>>,
+    ///Optional number of decimals per ticker, overriding the automatic precision
+    pub precision: HashMap<String, usize>,
+    ///User-defined color themes
+    pub themes: HashMap<String, theme::Theme>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            version: VERSION,
+            greenisup: true,
+            locale: None,
+            theme: "default".to_string(),
+            indicator: "none".to_string(),
+            emphasis: false,
+            plain: false,
+            gradient: false,
+            gradient_range: 5.0,
+            provider: "yahoo".to_string(),
+            refresh: 500,
+            benchmark: "SPY".to_string(),
+            risk_free_rate: 0.0,
+            watchlists: HashMap::new(),
+            precision: HashMap::new(),
+            themes: HashMap::new(),
+        }
+    }
+}
+
+///Keys Config knows about, anything else is likely a typo
+pub const KEYS: [&str; 16] = [
+    "version",
+    "greenisup",
+    "locale",
+    "theme",
+    "indicator",
+    "emphasis",
+    "plain",
+    "gradient",
+    "gradient_range",
+    "provider",
+    "refresh",
+    "benchmark",
+    "risk_free_rate",
+    "watchlists",
+    "precision",
+    "themes",
+];
+
+impl Config {
+    ///Returns name of the active theme, ZIGFI_THEME takes precedence over the file
+    pub fn theme(&self) -> String {
+        env("ZIGFI_THEME").unwrap_or_else(|| self.theme.clone())
+    }
+
+    ///Returns price provider, ZIGFI_PROVIDER takes precedence over the file
+    pub fn provider(&self) -> String {
+        env("ZIGFI_PROVIDER").unwrap_or_else(|| self.provider.clone())
+    }
+
+    ///Returns time between refreshes, ZIGFI_REFRESH takes precedence over the file
+    pub fn refresh(&self) -> Duration {
+        let millis = match env("ZIGFI_REFRESH") {
+            Some(value) => value
+                .parse()
+                .expect("ZIGFI_REFRESH must be a number of milliseconds."),
+            None => self.refresh,
+        };
+        Duration::from_millis(millis)
+    }
+}
+
+///Returns value of an environment variable if set and not empty
+fn env(key: &str) -> Option<String> {
+    std::env::var(key).ok().filter(|value| !value.is_empty())
+}
+
+///Uses provided file or profile instead of the default configuration for the rest of the run
+pub fn select(file: Option<String>, profile: Option<String>) {
+    let _ = SELECTED.set(resolve(file, profile));
+}
+
+///Returns location of the configuration file
+pub fn path() -> PathBuf {
+    match SELECTED.get() {
+        Some(path) => path.clone(),
+        None => resolve(None, None),
+    }
+}
+
+///Picks configuration file from arguments first, then ZIGFI_CONFIG and ZIGFI_PROFILE
+pub fn resolve(file: Option<String>, profile: Option<String>) -> PathBuf {
+    if let Some(file) = file.or_else(|| env("ZIGFI_CONFIG")) {
+        return PathBuf::from(file);
+    }
+    let directory = ProjectDirs::from("rs", "", "zigfi")
+        .expect("Failed to find zigfi configuration directory.")
+        .config_dir()
+        .to_path_buf();
+    match profile.or_else(|| env("ZIGFI_PROFILE")) {
+        Some(profile) => {
+            if !valid_profile(&profile) {
+                panic!("Profile names can only have letters, numbers, \"-\" and \"_\".");
+            }
+            directory.join(format!("zigfi-{}.toml", profile))
+        }
+        None => directory.join("zigfi.toml"),
+    }
+}
+
+///Checks that a profile name only has letters, numbers, "-" and "_"
+pub fn valid_profile(profile: &str) -> bool {
+    profile
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+///Loads configuration, creating it if missing and migrating it if written by an older version, exits with the error if it cannot be read
+pub fn load() -> Config {
+    try_load().unwrap_or_else(|error| {
+        eprintln!("{}", error);
+        std::process::exit(1);
+    })
+}
+
+///Loads configuration, creating it if missing, or returns why it cannot be read
+pub fn try_load() -> Result<Config, String> {
+    let path = path();
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(error) if error.kind() == ErrorKind::NotFound => {
+            let cfg = Config::default();
+            store(&cfg);
+            return Ok(cfg);
+        }
+        Err(error) => return Err(format!("Failed to read zigfi configuration: {}", error)),
+    };
+    let (cfg, migrated) =
+        parse(&text).map_err(|error| format!("{} ({})", error, path.display()))?;
+    if migrated {
+        store(&cfg);
+    }
+    Ok(cfg)
+}
+
+///Saves configuration
+pub fn store(cfg: &Config) {
+    let text = toml::to_string_pretty(cfg).expect("Failed to serialize zigfi configuration.");
+    store_text(&text);
+}
+
+///Saves configuration text through a temporary file so readers never see it half written
+pub fn store_text(text: &str) {
+    let path = path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).expect("Failed to create zigfi configuration directory.");
+    }
+    match fs::read_to_string(&path) {
+        Ok(current) if current == text => return,
+        Ok(current) => backup(&current),
+        Err(_) => {}
+    }
+    write(text);
+}
+
+///Replaces configuration file contents without taking a backup
+fn write(text: &str) {
+    let path = path();
+    let temporary = path.with_extension("toml.tmp");
+    let mut file = fs::File::create(&temporary).expect("Failed to save zigfi configuration.");
+    file.write_all(text.as_bytes())
+        .and_then(|_| file.sync_all())
+        .expect("Failed to save zigfi configuration.");
+    fs::rename(&temporary, &path).expect("Failed to save zigfi configuration.");
+}
+
+///Returns directory holding backups, next to the configuration file
+fn backup_dir() -> PathBuf {
+    let path = path();
+    path.parent()
+        .map(|parent| parent.join("backups"))
+        .unwrap_or_else(|| PathBuf::from("backups"))
+}
+
+///Returns prefix of backup names so profiles keep their backups apart
+fn backup_prefix() -> String {
+    let path = path();
+    let stem = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("zigfi");
+    format!("{}-", stem)
+}
+
+///Saves a timestamped copy of the configuration and drops the oldest ones beyond BACKUPS
+fn backup(text: &str) {
+    let directory = backup_dir();
+    fs::create_dir_all(&directory).expect("Failed to create zigfi backup directory.");
+    let stamp = chrono::Utc::now().format("%Y%m%dT%H%M%S%.3fZ");
+    let mut file = directory.join(format!("{}{}.toml", backup_prefix(), stamp));
+    let mut count = 1;
+    while file.exists() {
+        file = directory.join(format!("{}{}-{}.toml", backup_prefix(), stamp, count));
+        count += 1;
+    }
+    fs::write(&file, text).expect("Failed to back up zigfi configuration.");
+    for old in backups().into_iter().skip(BACKUPS) {
+        let _ = fs::remove_file(old);
+    }
+}
+
+///Returns backups of the configuration file, newest first
+pub fn backups() -> Vec<PathBuf> {
+    let prefix = backup_prefix();
+    let mut found: Vec<PathBuf> = match fs::read_dir(backup_dir()) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| stamp(name, &prefix).is_some())
+            })
+            .collect(),
+        Err(_) => vec![],
+    };
+    //Timestamps in names sort the same as the times they stand for
+    found.sort();
+    found.reverse();
+    found
+}
+
+///Returns when a backup was taken, read from its name
+pub fn backup_time(backup: &Path) -> Option<chrono::DateTime<chrono::Utc>> {
+    let name = backup.file_name()?.to_str()?;
+    stamp(name, &backup_prefix()).map(|time| chrono::Utc.from_utc_datetime(&time))
+}
+
+///Reads the time from a backup name, None unless the rest after the prefix is exactly a stamp and an optional counter
+///
+///Profiles with names like "2024" share the prefix's start, so anything looser mixes their backups in
+fn stamp(name: &str, prefix: &str) -> Option<chrono::NaiveDateTime> {
+    let rest = name.strip_prefix(prefix)?.strip_suffix(".toml")?;
+    let (stamp, counter) = (rest.get(..20)?, &rest[20..]);
+    let counter_valid = counter.is_empty()
+        || counter
+            .strip_prefix('-')
+            .is_some_and(|count| !count.is_empty() && count.chars().all(|c| c.is_ascii_digit()));
+    if !counter_valid {
+        return None;
+    }
+    chrono::NaiveDateTime::parse_from_str(stamp, "%Y%m%dT%H%M%S%.3fZ").ok()
+}
+
+///Replaces configuration with a backup, the replaced one is backed up first unless undoing
+pub fn restore(backup: &Path, undo: bool) -> Result<Config, String> {
+    let text = fs::read_to_string(backup)
+        .map_err(|error| format!("Failed to read backup {}: {}", backup.display(), error))?;
+    let (cfg, _) = parse(&text)?;
+    if undo {
+        write(&text);
+        fs::remove_file(backup)
+            .map_err(|error| format!("Failed to remove used backup: {}", error))?;
+    } else {
+        store_text(&text);
+    }
+    Ok(cfg)
+}
+
+///Exclusive lock on the configuration, released when dropped
+pub struct Lock(fs::File);
+
+impl Drop for Lock {
+    fn drop(&mut self) {
+        let _ = self.0.unlock();
+    }
+}
+
+///Locks the configuration for a read-modify-write, waiting while another zigfi process holds it
+///
+///Take it before load and drop it right after store, never while waiting on the user to quit
+pub fn lock() -> Lock {
+    let path = path().with_extension("toml.lock");
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).expect("Failed to create zigfi configuration directory.");
+    }
+    let file = fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&path)
+        .expect("Failed to open zigfi configuration lock.");
+    file.lock().expect("Failed to lock zigfi configuration.");
+    Lock(file)
+}
+
+///Parses configuration text, returns whether it had to be migrated to the current version
+pub fn parse(text: &str) -> Result<(Config, bool), String> {
+    let mut value: toml::Value =
+        toml::from_str(text).map_err(|error| format!("Invalid zigfi configuration: {}", error))?;
+    let table = value
+        .as_table_mut()
+        .ok_or("Invalid zigfi configuration: expected a table")?;
+    let version = match table.get("version") {
+        None => 1,
+        Some(toml::Value::Integer(version)) if *version >= 1 => *version as u32,
+        Some(_) => {
+            return Err(format!(
+                "Invalid zigfi configuration: \"version\" must be a positive number at line {}",
+                line_of(text, "version")
+            ))
+        }
+    };
+    //Newer files may have keys this version does not know yet
+    if version <= VERSION {
+        let known = |key: &str| {
+            KEYS.contains(&key)
+                && (version >= 3 || !SINCE_V3.contains(&key))
+                && (version >= 2 || key != "version")
+        };
+        if let Some(key) = table.keys().find(|key| !known(key)) {
+            return Err(format!(
+                "Invalid zigfi configuration: unknown key \"{}\" at line {}",
+                key,
+                line_of(text, key)
+            ));
+        }
+        if let Some(toml::Value::Table(themes)) = table.get("themes") {
+            for (name, colors) in themes {
+                let unknown = colors.as_table().and_then(|colors| {
+                    colors
+                        .keys()
+                        .find(|key| !theme::KEYS.contains(&key.as_str()))
+                });
+                if let Some(key) = unknown {
+                    return Err(format!(
+                        "Invalid zigfi configuration: unknown key \"{}\" in theme \"{}\" at line {}",
+                        key,
+                        name,
+                        line_of(text, key)
+                    ));
+                }
+            }
+        }
+    }
+    if version >= VERSION {
+        //Parsing the text itself keeps the line of a bad value in the error
+        let cfg: Config = toml::from_str(text)
+            .map_err(|error| format!("Invalid zigfi configuration: {}", error))?;
+        return Ok((cfg, false));
+    }
+    for migration in MIGRATIONS.iter().skip(version as usize - 1) {
+        migration(table);
+    }
+    table.insert("version".to_string(), toml::Value::Integer(VERSION as i64));
+    let cfg: Config = value
+        .try_into()
+        .map_err(|error| format!("Invalid zigfi configuration: {}", error))?;
+    Ok((cfg, true))
+}
+
+///Checks settings that parse fine but cannot be used, like a theme that does not exist
+pub fn validate(cfg: &Config) -> Result<(), String> {
+    let theme = cfg.theme();
+    if !cfg.themes.contains_key(&theme) && theme::Theme::builtin(&theme).is_none() {
+        return Err(format!("Theme \"{}\" does not exist.", theme));
+    }
+    for (name, theme) in cfg.themes.iter() {
+        theme::Palette::new(theme, theme::Depth::TrueColor)
+            .map_err(|error| format!("{} ({})", error, name))?;
+    }
+    if format::Indicator::from_name(&cfg.indicator).is_none() {
+        return Err(format!(
+            "Indicator \"{}\" does not exist. Use none, arrows or signs.",
+            cfg.indicator
+        ));
+    }
+    if !PROVIDERS.contains(&cfg.provider().as_str()) {
+        return Err(format!(
+            "Provider \"{}\" does not exist. Available: {}.",
+            cfg.provider(),
+            PROVIDERS.join(", ")
+        ));
+    }
+    if cfg.gradient_range <= 0.0 {
+        return Err("\"gradient_range\" must be above zero.".to_string());
+    }
+    if cfg.benchmark.trim().is_empty() {
+        return Err("\"benchmark\" must be a ticker.".to_string());
+    }
+    Ok(())
+}
+
+///Splits a dotted key like "themes.mine.up", tickers in map keys may have dots themselves
+fn split_key(key: &str) -> Vec<&str> {
+    let top = key.split('.').next().unwrap_or("");
+    let depth = match top {
+        "themes" => 3,
+        "watchlists" | "precision" => 2,
+        _ => 1,
+    };
+    key.splitn(depth, '.').collect()
+}
+
+///Returns value of a dotted key as text
+pub fn get(cfg: &Config, key: &str) -> Result<String, String> {
+    let mut value =
+        toml::Value::try_from(cfg).map_err(|_| "Failed to read zigfi configuration.")?;
+    for part in split_key(key) {
+        value = value
+            .get(part)
+            .cloned()
+            .ok_or(format!("Key \"{}\" is not set.", key))?;
+    }
+    Ok(match value {
+        toml::Value::String(text) => text,
+        toml::Value::Table(_) => toml::to_string_pretty(&value).unwrap_or_default(),
+        other => other.to_string(),
+    })
+}
+
+///Returns configuration with a dotted key set, values are read as TOML and fall back to plain text
+pub fn set(cfg: &Config, key: &str, value: &str) -> Result<Config, String> {
+    let parsed = toml::from_str::<toml::value::Table>(&format!("value = {}", value))
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| toml::Value::String(value.to_string()));
+    let mut root = toml::Value::try_from(cfg).map_err(|_| "Failed to read zigfi configuration.")?;
+    let parts = split_key(key);
+    if !KEYS.contains(&parts[0]) {
+        return Err(format!("Unknown key \"{}\".", parts[0]));
+    }
+    let mut table = root
+        .as_table_mut()
+        .ok_or("Failed to read zigfi configuration.")?;
+    for part in &parts[..parts.len() - 1] {
+        table = table
+            .entry(part.to_string())
+            .or_insert_with(|| toml::Value::Table(toml::value::Table::new()))
+            .as_table_mut()
+            .ok_or(format!("\"{}\" is not a table.", part))?;
+    }
+    table.insert(parts[parts.len() - 1].to_string(), parsed);
+    let updated: Config = root
+        .try_into()
+        .map_err(|error| format!("Invalid value for \"{}\": {}", key, error))?;
+    validate(&updated)?;
+    Ok(updated)
+}
+
+///Returns line where a top-level key is set, counting from one
+fn line_of(text: &str, key: &str) -> usize {
+    text.lines()
+        .position(|line| {
+            let line = line.trim_start();
+            line.strip_prefix(key)
+                .is_some_and(|rest| rest.trim_start().starts_with('='))
+                || line.starts_with(&format!("[{}]", key))
+                || line.starts_with(&format!("[{}.", key))
+        })
+        .map_or(0, |index| index + 1)
+}
+
+///Version 1 had no version key and stored East Asian colors only as greenisup = false
+fn from_v1(table: &mut toml::value::Table) {
+    let swapped = table.get("greenisup") == Some(&toml::Value::Boolean(false));
+    let default_theme = table
+        .get("theme")
+        .is_none_or(|theme| theme.as_str() == Some("default"));
+    if swapped && default_theme {
+        table.insert("greenisup".to_string(), toml::Value::Boolean(true));
+        table.insert(
+            "theme".to_string(),
+            toml::Value::String("east-asian".to_string()),
+        );
+    }
+}
+
+///Version 2 files lack provider and refresh, they get the defaults written out
+fn from_v2(table: &mut toml::value::Table) {
+    let defaults = Config::default();
+    table
+        .entry("provider")
+        .or_insert(toml::Value::String(defaults.provider));
+    table
+        .entry("refresh")
+        .or_insert(toml::Value::Integer(defaults.refresh as i64));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn baseline_file_migrates_swapped_colors_to_east_asian() {
+        let (cfg, migrated) =
+            parse("greenisup = false\n\n[watchlists]\ndefault = [\"AAPL\", \"MSFT\"]\n")
+                .expect("Baseline file rejected");
+        assert!(migrated);
+        assert_eq!(cfg.version, VERSION);
+        assert!(cfg.greenisup);
+        assert_eq!(cfg.theme, "east-asian");
+        assert_eq!(cfg.watchlists["default"], vec!["AAPL", "MSFT"]);
+    }
+
+    #[test]
+    fn baseline_file_keeps_green_up_and_custom_theme() {
+        let (cfg, _) = parse("greenisup = false\ntheme = \"mine\"\n[watchlists]\n")
+            .expect("Baseline file rejected");
+        assert!(!cfg.greenisup);
+        assert_eq!(cfg.theme, "mine");
+    }
+
+    #[test]
+    fn version_2_file_gets_provider_and_refresh() {
+        let text = "version = 2\ngreenisup = true\ntheme = \"monochrome\"\n[watchlists]\nfx = [\"EURUSD=X\"]\n";
+        let (cfg, migrated) = parse(text).expect("Version 2 file rejected");
+        assert!(migrated);
+        assert_eq!(cfg.version, VERSION);
+        assert_eq!(cfg.provider, "yahoo");
+        assert_eq!(cfg.refresh, 500);
+        assert_eq!(cfg.theme, "monochrome");
+        assert_eq!(cfg.watchlists["fx"], vec!["EURUSD=X"]);
+    }
+
+    #[test]
+    fn current_file_is_not_migrated() {
+        let (cfg, migrated) =
+            parse("version = 3\nrefresh = 1000\n").expect("Current file rejected");
+        assert!(!migrated);
+        assert_eq!(cfg.refresh, 1000);
+    }
+
+    #[test]
+    fn unknown_key_is_reported_with_its_line() {
+        let error = parse("version = 3\ngreenisup = true\ngreenisUp = false\n")
+            .err()
+            .expect("Unknown key accepted");
+        assert_eq!(
+            error,
+            "Invalid zigfi configuration: unknown key \"greenisUp\" at line 3"
+        );
+    }
+
+    #[test]
+    fn unknown_key_in_older_file_is_reported() {
+        let error = parse("greenisUp = false\n[watchlists]\n")
+            .err()
+            .expect("Unknown key accepted");
+        assert_eq!(
+            error,
+            "Invalid zigfi configuration: unknown key \"greenisUp\" at line 1"
+        );
+        assert!(parse("version = 2\nrefresh = 100\n").is_err());
+    }
+
+    #[test]
+    fn unknown_theme_key_is_reported() {
+        let error = parse("version = 3\n\n[themes.mine]\nup = \"green\"\ndonw = \"red\"\n")
+            .err()
+            .expect("Unknown theme key accepted");
+        assert_eq!(
+            error,
+            "Invalid zigfi configuration: unknown key \"donw\" in theme \"mine\" at line 5"
+        );
+    }
+}
@@ -1,6 +1,106 @@
 #![forbid(unsafe_code)]
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Datelike, Local, NaiveDate, Utc};
+
+//Order of day, month and year in dates
+#[derive(Clone, Copy, PartialEq)]
+pub enum DateOrder {
+    DayMonthYear,
+    MonthDayYear,
+    YearMonthDay,
+}
+
+//Number and date conventions used when formatting human-readable output
+#[derive(Clone, Copy)]
+pub struct Locale {
+    pub decimal: char,
+    pub group: char,
+    pub date_order: DateOrder,
+    pub date_separator: char,
+    pub twenty_four_hour: bool,
+}
+
+impl Locale {
+    //Builds locale from a POSIX-style tag like "de_DE.UTF-8" or "en-US"
+    pub fn from_tag(tag: &str) -> Locale {
+        let tag = tag.split(['.', '@']).next().unwrap_or("");
+        let mut parts = tag.split(['_', '-']);
+        let language = parts.next().unwrap_or("").to_ascii_lowercase();
+        let region = parts.next().unwrap_or("").to_ascii_uppercase();
+        let (decimal, group) = match (language.as_str(), region.as_str()) {
+            ("de", "CH") | ("it", "CH") | ("fr", "CH") => ('.', '\''),
+            (
+                "de" | "nl" | "it" | "es" | "pt" | "da" | "id" | "tr" | "el" | "ro" | "hr" | "sl",
+                _,
+            ) => (',', '.'),
+            (
+                "fr" | "ru" | "pl" | "cs" | "sk" | "sv" | "fi" | "nb" | "no" | "uk" | "hu" | "bg",
+                _,
+            ) => (',', '\u{202f}'),
+            _ => ('.', ','),
+        };
+        let (date_order, date_separator) = match (language.as_str(), region.as_str()) {
+            ("en", "US") | ("en", "PH") | ("", _) | ("c", _) | ("posix", _) => {
+                (DateOrder::MonthDayYear, '/')
+            }
+            ("ja" | "zh" | "ko" | "hu" | "lt" | "sv", _) => (DateOrder::YearMonthDay, '-'),
+            (
+                "de" | "ru" | "pl" | "cs" | "sk" | "fi" | "nb" | "no" | "uk" | "tr" | "ro" | "hr"
+                | "sl" | "bg" | "da",
+                _,
+            ) => (DateOrder::DayMonthYear, '.'),
+            ("nl", _) => (DateOrder::DayMonthYear, '-'),
+            _ => (DateOrder::DayMonthYear, '/'),
+        };
+        let twenty_four_hour = !matches!(
+            (language.as_str(), region.as_str()),
+            ("en", "US")
+                | ("en", "PH")
+                | ("en", "AU")
+                | ("en", "CA")
+                | ("", _)
+                | ("c", _)
+                | ("posix", _)
+        );
+        Locale {
+            decimal,
+            group,
+            date_order,
+            date_separator,
+            twenty_four_hour,
+        }
+    }
+
+    //Detects locale from LC_ALL, LC_NUMERIC and LANG in that order, falling back to en_US
+    pub fn detect() -> Locale {
+        let tag = ["LC_ALL", "LC_NUMERIC", "LANG"]
+            .iter()
+            .filter_map(|key| std::env::var(key).ok())
+            .find(|value| !value.is_empty())
+            .unwrap_or_else(|| "en_US".to_string());
+        Locale::from_tag(&tag)
+    }
+}
+
+//Direction marker shown next to a change so it reads without color
+#[derive(Clone, Copy, PartialEq)]
+pub enum Indicator {
+    None,
+    Arrows,
+    Signs,
+}
+
+impl Indicator {
+    //Returns indicator for names used in the configuration
+    pub fn from_name(name: &str) -> Option<Indicator> {
+        match name.to_ascii_lowercase().as_str() {
+            "none" => Some(Indicator::None),
+            "arrows" => Some(Indicator::Arrows),
+            "signs" => Some(Indicator::Signs),
+            _ => None,
+        }
+    }
+}
 
 //Calculates percentage difference between two f64 values
 pub fn prcnt(n1: f64, n2: f64) -> f64 {
@@ -11,9 +111,88 @@ pub fn prcnt(n1: f64, n2: f64) -> f64 {
     sub / div * 100_f64
 }
 
-//Reduces f64 length to 2 decimals
-pub fn reduc(num: f64) -> f64 {
-    (num * 100.0).round() / 100.0
+//Returns the number of decimals a price needs based on Yahoo's price hint and its magnitude
+pub fn decimals(num: f64, hint: usize) -> usize {
+    let magnitude = num.abs();
+    let needed = if magnitude >= 1.0 || magnitude == 0.0 {
+        2
+    } else {
+        //Keeps four significant digits for sub-unit prices like DOGE, SHIB or FX pairs
+        (-magnitude.log10()).ceil() as usize + 3
+    };
+    needed.max(hint).min(10)
+}
+
+//Formats price with fixed decimals and the locale's separators
+pub fn price(num: f64, decimals: usize, locale: &Locale) -> String {
+    let fixed = plain_price(num, decimals);
+    let (sign, unsigned) = match fixed.strip_prefix('-') {
+        Some(rest) => ("-", rest),
+        None => ("", fixed.as_str()),
+    };
+    let (whole, fraction) = match unsigned.split_once('.') {
+        Some((whole, fraction)) => (whole, Some(fraction)),
+        None => (unsigned, None),
+    };
+    let mut grouped = String::new();
+    for (index, digit) in whole.chars().enumerate() {
+        if index > 0 && (whole.len() - index) % 3 == 0 {
+            grouped.push(locale.group);
+        }
+        grouped.push(digit);
+    }
+    match fraction {
+        Some(fraction) => format!("{}{}{}{}", sign, grouped, locale.decimal, fraction),
+        None => format!("{}{}", sign, grouped),
+    }
+}
+
+//Formats price with fixed decimals and no separators for machine-readable output
+pub fn plain_price(num: f64, decimals: usize) -> String {
+    format!("{:.*}", decimals, num)
+}
+
+//Formats percentage difference with two fixed decimals and the locale's decimal separator
+pub fn percent(num: f64, locale: &Locale) -> String {
+    plain_percent(num).replace('.', &locale.decimal.to_string())
+}
+
+//Formats percentage difference with the locale's decimal separator and a direction indicator
+pub fn change(num: f64, locale: &Locale, indicator: Indicator) -> String {
+    let text = percent(num, locale);
+    let signed = if num > 0.0 {
+        format!("+{}%", text)
+    } else {
+        format!("{}%", text)
+    };
+    match indicator {
+        Indicator::None => format!("{}%", text),
+        Indicator::Signs => signed,
+        Indicator::Arrows if num > 0.0 => format!("\u{25b2} {}", signed),
+        Indicator::Arrows if num < 0.0 => format!("\u{25bc} {}", signed),
+        Indicator::Arrows => format!("\u{25a0} {}", signed),
+    }
+}
+
+//Formats percentage difference with two fixed decimals for machine-readable output
+pub fn plain_percent(num: f64) -> String {
+    format!("{:.2}", num)
+}
+
+//Formats local date and time following the locale's conventions
+pub fn timestamp(time: DateTime<Local>, locale: &Locale) -> String {
+    let separator = locale.date_separator;
+    let date = match locale.date_order {
+        DateOrder::DayMonthYear => format!("%d{0}%m{0}%Y", separator),
+        DateOrder::MonthDayYear => format!("%m{0}%d{0}%Y", separator),
+        DateOrder::YearMonthDay => format!("%Y{0}%m{0}%d", separator),
+    };
+    let clock = if locale.twenty_four_hour {
+        "%H:%M:%S"
+    } else {
+        "%I:%M:%S %p"
+    };
+    time.format(&format!("{} {}", date, clock)).to_string()
 }
 
 //Returns DateTime Utc value based on given value
@@ -27,3 +206,110 @@ pub fn get_time(interval: &str) -> (DateTime<Utc>, DateTime<Utc>) {
     }
     (now - back, now)
 }
+
+//Returns the same day some months earlier, or the month's last day if it is shorter
+pub fn months_back(date: NaiveDate, months: u32) -> NaiveDate {
+    let total = date.year() * 12 + date.month0() as i32 - months as i32;
+    let (year, month) = (total.div_euclid(12), total.rem_euclid(12) as u32 + 1);
+    (1..=date.day())
+        .rev()
+        .find_map(|day| NaiveDate::from_ymd_opt(year, month, day))
+        .expect("Invalid date.")
+}
+
+//Returns percentage return from the first value to the second
+pub fn growth(from: f64, to: f64) -> f64 {
+    (to / from - 1.0) * 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn decimals_keep_four_significant_digits_below_one() {
+        assert_eq!(decimals(1795.5, 2), 2);
+        assert_eq!(decimals(0.0812, 2), 5);
+        assert_eq!(decimals(0.0000099, 2), 9);
+        assert_eq!(decimals(-0.5, 2), 4);
+        assert_eq!(decimals(0.0, 2), 2);
+        assert_eq!(decimals(1.2345, 4), 4);
+        assert_eq!(decimals(0.000000000001, 2), 10);
+    }
+
+    #[test]
+    fn price_formats_doge_and_shib() {
+        let locale = Locale::from_tag("en_US");
+        assert_eq!(price(0.0812, decimals(0.0812, 2), &locale), "0.08120");
+        assert_eq!(
+            price(0.0000099, decimals(0.0000099, 2), &locale),
+            "0.000009900"
+        );
+    }
+
+    #[test]
+    fn price_groups_thousands_by_locale() {
+        assert_eq!(price(1795.5, 2, &Locale::from_tag("en_US")), "1,795.50");
+        assert_eq!(
+            price(1795.5, 2, &Locale::from_tag("de_DE.UTF-8")),
+            "1.795,50"
+        );
+        assert_eq!(
+            price(1795.5, 2, &Locale::from_tag("fr_FR")),
+            "1\u{202f}795,50"
+        );
+        assert_eq!(price(1234567.0, 0, &Locale::from_tag("en_US")), "1,234,567");
+        assert_eq!(price(999.0, 0, &Locale::from_tag("en_US")), "999");
+    }
+
+    #[test]
+    fn price_keeps_sign_out_of_grouping() {
+        let locale = Locale::from_tag("en_US");
+        assert_eq!(price(-123456.789, 2, &locale), "-123,456.79");
+        assert_eq!(price(-999.5, 0, &locale), "-1,000");
+    }
+
+    #[test]
+    fn percent_uses_locale_decimal() {
+        assert_eq!(percent(-1.234, &Locale::from_tag("en_US")), "-1.23");
+        assert_eq!(percent(-1.234, &Locale::from_tag("de_DE")), "-1,23");
+        assert_eq!(percent(0.5, &Locale::from_tag("fr_FR")), "0,50");
+    }
+
+    #[test]
+    fn from_tag_reads_language_and_region() {
+        let us = Locale::from_tag("en_US.UTF-8");
+        assert!(us.decimal == '.' && us.group == ',');
+        assert!(us.date_order == DateOrder::MonthDayYear && !us.twenty_four_hour);
+        let de = Locale::from_tag("de-DE");
+        assert!(de.decimal == ',' && de.group == '.');
+        assert!(de.date_order == DateOrder::DayMonthYear && de.date_separator == '.');
+        let swiss = Locale::from_tag("de_CH");
+        assert!(swiss.decimal == '.' && swiss.group == '\'');
+    }
+
+    #[test]
+    fn timestamp_follows_locale_dates_and_clock() {
+        let time = Local
+            .from_local_datetime(
+                &NaiveDate::from_ymd_opt(2024, 3, 7)
+                    .and_then(|date| date.and_hms_opt(14, 5, 9))
+                    .expect("Invalid date"),
+            )
+            .single()
+            .expect("Ambiguous local time");
+        assert_eq!(
+            timestamp(time, &Locale::from_tag("en_US")),
+            "03/07/2024 02:05:09 PM"
+        );
+        assert_eq!(
+            timestamp(time, &Locale::from_tag("de_DE")),
+            "07.03.2024 14:05:09"
+        );
+        assert_eq!(
+            timestamp(time, &Locale::from_tag("fr_FR")),
+            "07/03/2024 14:05:09"
+        );
+    }
+}
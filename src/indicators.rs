@@ -0,0 +1,445 @@
+#![forbid(unsafe_code)]
+
+use yahoo_finance_api::Quote;
+
+///MACD line, its signal line and the difference between them
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Macd {
+    pub line: f64,
+    pub signal: f64,
+    pub histogram: f64,
+}
+
+///Bollinger bands around a simple moving average
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bands {
+    pub lower: f64,
+    pub middle: f64,
+    pub upper: f64,
+}
+
+///Indicator that can be shown as a column next to a ticker's price
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Column {
+    Sma(usize),
+    Ema(usize),
+    Rsi(usize),
+    Macd,
+    MacdSignal,
+    MacdHistogram,
+    Upper(usize),
+    Lower(usize),
+    Atr(usize),
+}
+
+impl Column {
+    ///Reads names like sma50, rsi14 or macd, a missing period uses the usual one
+    pub fn from_name(name: &str) -> Option<Column> {
+        let name = name.trim().to_lowercase();
+        let split = name
+            .find(|c: char| c.is_ascii_digit())
+            .unwrap_or(name.len());
+        let (kind, digits) = name.split_at(split);
+        let period = |default: usize| match digits {
+            "" => Some(default),
+            _ => digits.parse().ok().filter(|period| *period > 0),
+        };
+        match kind {
+            "sma" => period(20).map(Column::Sma),
+            "ema" => period(20).map(Column::Ema),
+            "rsi" => period(14).map(Column::Rsi),
+            "bbupper" => period(20).map(Column::Upper),
+            "bblower" => period(20).map(Column::Lower),
+            "atr" => period(14).map(Column::Atr),
+            "macd" if digits.is_empty() => Some(Column::Macd),
+            "macdsignal" if digits.is_empty() => Some(Column::MacdSignal),
+            "macdhist" if digits.is_empty() => Some(Column::MacdHistogram),
+            _ => None,
+        }
+    }
+
+    ///Name used for headers and machine-readable keys
+    pub fn name(&self) -> String {
+        match self {
+            Column::Sma(period) => format!("sma{}", period),
+            Column::Ema(period) => format!("ema{}", period),
+            Column::Rsi(period) => format!("rsi{}", period),
+            Column::Macd => "macd".to_string(),
+            Column::MacdSignal => "macdsignal".to_string(),
+            Column::MacdHistogram => "macdhist".to_string(),
+            Column::Upper(period) => format!("bbupper{}", period),
+            Column::Lower(period) => format!("bblower{}", period),
+            Column::Atr(period) => format!("atr{}", period),
+        }
+    }
+
+    ///Whether the value is in the ticker's currency rather than from 0 to 100
+    pub fn is_price(&self) -> bool {
+        !matches!(self, Column::Rsi(_))
+    }
+
+    ///Number of bars before the value stops depending on where the history starts
+    fn warmup(&self) -> usize {
+        match self {
+            Column::Sma(period) | Column::Upper(period) | Column::Lower(period) => *period,
+            //Smoothed averages keep a trace of their seed, three periods make it negligible
+            Column::Ema(period) | Column::Rsi(period) | Column::Atr(period) => period * 3 + 1,
+            Column::Macd | Column::MacdSignal | Column::MacdHistogram => 26 * 3 + 9,
+        }
+    }
+
+    ///Whether the value is on the price's scale and can be drawn over it on a chart
+    pub fn is_overlay(&self) -> bool {
+        matches!(
+            self,
+            Column::Sma(_) | Column::Ema(_) | Column::Upper(_) | Column::Lower(_)
+        )
+    }
+
+    ///Returns the value at every bar, None where the history is too short
+    pub fn series(&self, bars: &[Quote]) -> Vec<Option<f64>> {
+        let closes: Vec<f64> = bars.iter().map(|bar| bar.close).collect();
+        match *self {
+            Column::Sma(period) => sma(&closes, period),
+            Column::Ema(period) => ema(&closes, period),
+            Column::Rsi(period) => rsi(&closes, period),
+            Column::Macd | Column::MacdSignal | Column::MacdHistogram => macd(&closes, 12, 26, 9)
+                .into_iter()
+                .map(|macd| {
+                    macd.map(|macd| match self {
+                        Column::Macd => macd.line,
+                        Column::MacdSignal => macd.signal,
+                        _ => macd.histogram,
+                    })
+                })
+                .collect(),
+            Column::Upper(period) | Column::Lower(period) => bollinger(&closes, period, 2.0)
+                .into_iter()
+                .map(|bands| {
+                    bands.map(|bands| match self {
+                        Column::Upper(_) => bands.upper,
+                        _ => bands.lower,
+                    })
+                })
+                .collect(),
+            Column::Atr(period) => {
+                let highs: Vec<f64> = bars.iter().map(|bar| bar.high).collect();
+                let lows: Vec<f64> = bars.iter().map(|bar| bar.low).collect();
+                atr(&highs, &lows, &closes, period)
+            }
+        }
+    }
+
+    ///Returns the value at the last bar, None if the history is too short
+    pub fn latest(&self, bars: &[Quote]) -> Option<f64> {
+        self.series(bars).last().copied().flatten()
+    }
+}
+
+///Moves the last daily bar to the latest price, or starts a new bar once a day has passed since it opened
+pub fn update(bars: &mut Vec<Quote>, price: f64, time: u64) {
+    match bars.last_mut() {
+        Some(last) if time < last.timestamp + 24 * 3600 => {
+            last.close = price;
+            last.high = last.high.max(price);
+            last.low = last.low.min(price);
+        }
+        _ => bars.push(Quote {
+            timestamp: time,
+            open: price,
+            high: price,
+            low: price,
+            volume: 0,
+            close: price,
+            adjclose: price,
+        }),
+    }
+}
+
+///Returns the shortest history range with enough daily bars for every column
+pub fn range(columns: &[Column]) -> &'static str {
+    //About 250 trading days a year
+    match columns.iter().map(Column::warmup).max().unwrap_or(0) {
+        0..=250 => "1y",
+        251..=500 => "2y",
+        501..=1250 => "5y",
+        _ => "max",
+    }
+}
+
+///Simple moving average, None until period values are available
+pub fn sma(values: &[f64], period: usize) -> Vec<Option<f64>> {
+    (0..values.len())
+        .map(|index| {
+            (period > 0 && index + 1 >= period)
+                .then(|| values[index + 1 - period..=index].iter().sum::<f64>() / period as f64)
+        })
+        .collect()
+}
+
+///Exponential moving average seeded with the simple average of the first period values
+pub fn ema(values: &[f64], period: usize) -> Vec<Option<f64>> {
+    smooth(values, period, 2.0 / (period as f64 + 1.0))
+}
+
+///Relative strength index with Wilder's smoothing of gains and losses
+pub fn rsi(closes: &[f64], period: usize) -> Vec<Option<f64>> {
+    let changes: Vec<f64> = closes.windows(2).map(|pair| pair[1] - pair[0]).collect();
+    let gains: Vec<f64> = changes.iter().map(|change| change.max(0.0)).collect();
+    let losses: Vec<f64> = changes.iter().map(|change| (-change).max(0.0)).collect();
+    let weight = 1.0 / period as f64;
+    let gains = smooth(&gains, period, weight);
+    let losses = smooth(&losses, period, weight);
+    //The first close has no change before it
+    let mut values = vec![None; closes.len().min(1)];
+    values.extend(
+        gains
+            .into_iter()
+            .zip(losses)
+            .map(|(gain, loss)| match (gain?, loss?) {
+                (_, 0.0) => Some(100.0),
+                (gain, loss) => Some(100.0 - 100.0 / (1.0 + gain / loss)),
+            }),
+    );
+    values
+}
+
+///Moving average convergence divergence, the signal line averages the MACD line once it starts
+pub fn macd(values: &[f64], fast: usize, slow: usize, signal: usize) -> Vec<Option<Macd>> {
+    let lines: Vec<Option<f64>> = ema(values, fast)
+        .into_iter()
+        .zip(ema(values, slow))
+        .map(|(fast, slow)| Some(fast? - slow?))
+        .collect();
+    let start = lines
+        .iter()
+        .position(Option::is_some)
+        .unwrap_or(lines.len());
+    let defined: Vec<f64> = lines[start..].iter().flatten().copied().collect();
+    let mut signals = vec![None; start];
+    signals.extend(ema(&defined, signal));
+    lines
+        .into_iter()
+        .zip(signals)
+        .map(|(line, signal)| {
+            let (line, signal) = (line?, signal?);
+            Some(Macd {
+                line,
+                signal,
+                histogram: line - signal,
+            })
+        })
+        .collect()
+}
+
+///Bands the provided number of standard deviations around the simple moving average
+pub fn bollinger(values: &[f64], period: usize, width: f64) -> Vec<Option<Bands>> {
+    sma(values, period)
+        .into_iter()
+        .enumerate()
+        .map(|(index, middle)| {
+            let middle = middle?;
+            let window = &values[index + 1 - period..=index];
+            let variance = window
+                .iter()
+                .map(|value| (value - middle).powi(2))
+                .sum::<f64>()
+                / period as f64;
+            let deviation = variance.sqrt() * width;
+            Some(Bands {
+                lower: middle - deviation,
+                middle,
+                upper: middle + deviation,
+            })
+        })
+        .collect()
+}
+
+///Average true range with Wilder's smoothing, gaps from the previous close count as range
+pub fn atr(highs: &[f64], lows: &[f64], closes: &[f64], period: usize) -> Vec<Option<f64>> {
+    let ranges: Vec<f64> = (0..closes.len())
+        .map(|index| {
+            let range = highs[index] - lows[index];
+            match index.checked_sub(1).map(|previous| closes[previous]) {
+                Some(close) => range
+                    .max((highs[index] - close).abs())
+                    .max((lows[index] - close).abs()),
+                None => range,
+            }
+        })
+        .collect();
+    smooth(&ranges, period, 1.0 / period as f64)
+}
+
+///Moves an average toward every new value by weight, starting from the simple average
+fn smooth(values: &[f64], period: usize, weight: f64) -> Vec<Option<f64>> {
+    let mut average: Option<f64> = None;
+    values
+        .iter()
+        .enumerate()
+        .map(|(index, value)| {
+            average = match average {
+                Some(previous) => Some(previous + weight * (value - previous)),
+                None if period > 0 && index + 1 == period => {
+                    Some(values[..period].iter().sum::<f64>() / period as f64)
+                }
+                None => None,
+            };
+            average
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: Option<f64>, expected: f64) {
+        let actual = actual.expect("No value");
+        assert!(
+            (actual - expected).abs() < 0.005,
+            "{} is not {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn sma_averages_the_last_values() {
+        assert_eq!(
+            sma(&[1.0, 2.0, 3.0, 4.0, 5.0], 3),
+            vec![None, None, Some(2.0), Some(3.0), Some(4.0)]
+        );
+    }
+
+    #[test]
+    fn ema_matches_stockcharts_example() {
+        let closes = [
+            22.27, 22.19, 22.08, 22.17, 22.18, 22.13, 22.23, 22.43, 22.24, 22.29, 22.15, 22.39,
+            22.38, 22.61, 23.36, 24.05, 23.75, 23.83, 23.95, 23.63,
+        ];
+        let values = ema(&closes, 10);
+        assert!(values[..9].iter().all(Option::is_none));
+        let expected = [
+            22.22, 22.21, 22.24, 22.27, 22.33, 22.52, 22.80, 22.97, 23.13, 23.28, 23.34,
+        ];
+        for (value, expected) in values[9..].iter().zip(expected) {
+            assert_close(*value, expected);
+        }
+    }
+
+    #[test]
+    fn rsi_matches_wilders_example() {
+        //StockCharts' RSI spreadsheet, closes as published and unrounded
+        let closes = [
+            44.3389, 44.0902, 44.1497, 43.6124, 44.3278, 44.8264, 45.0955, 45.4245, 45.8433,
+            46.0826, 45.8931, 46.0328, 45.6140, 46.2820, 46.2820, 46.0028, 46.0328, 46.4116,
+            46.2222, 45.6439,
+        ];
+        let values = rsi(&closes, 14);
+        assert_eq!(values.len(), closes.len());
+        assert!(values[..14].iter().all(Option::is_none));
+        for (value, expected) in values[14..]
+            .iter()
+            .zip([70.53, 66.32, 66.55, 69.41, 66.36, 57.97])
+        {
+            assert_close(*value, expected);
+        }
+    }
+
+    #[test]
+    fn rsi_is_100_without_losses() {
+        let closes: Vec<f64> = (1..=20).map(f64::from).collect();
+        assert_eq!(rsi(&closes, 14).last().copied().flatten(), Some(100.0));
+    }
+
+    #[test]
+    fn macd_of_steady_trend_is_the_lag_between_averages() {
+        //Averages of a straight line trail it by (period - 1) / 2, so 12 and 26 differ by 7
+        let values: Vec<f64> = (0..100).map(f64::from).collect();
+        let results = macd(&values, 12, 26, 9);
+        assert!(results[..33].iter().all(Option::is_none));
+        let last = results.last().copied().flatten().expect("No value");
+        assert_close(Some(last.line), 7.0);
+        assert_close(Some(last.signal), 7.0);
+        assert_close(Some(last.histogram), 0.0);
+    }
+
+    #[test]
+    fn bollinger_uses_population_deviation() {
+        let values = [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0];
+        let bands = bollinger(&values, 8, 2.0);
+        assert_eq!(
+            bands.last().copied().flatten(),
+            Some(Bands {
+                lower: 1.0,
+                middle: 5.0,
+                upper: 9.0
+            })
+        );
+    }
+
+    #[test]
+    fn atr_counts_gaps_from_previous_close() {
+        let highs = [10.0, 11.0, 12.0, 11.5, 10.0, 15.0];
+        let lows = [8.0, 9.0, 10.0, 9.5, 7.0, 14.0];
+        let closes = [9.0, 10.5, 11.0, 10.0, 8.0, 14.5];
+        let values = atr(&highs, &lows, &closes, 3);
+        assert_eq!(values[..2], [None, None]);
+        assert_close(values[2], 2.0);
+        assert_close(values[3], 2.0);
+        assert_close(values[4], 2.3333);
+        assert_close(values[5], 3.8889);
+    }
+
+    #[test]
+    fn update_moves_last_bar_then_starts_a_new_one() {
+        let bar = |timestamp: u64, close: f64| Quote {
+            timestamp,
+            open: close,
+            high: close,
+            low: close,
+            volume: 0,
+            close,
+            adjclose: close,
+        };
+        let mut bars = vec![bar(0, 10.0), bar(86_400, 11.0)];
+        update(&mut bars, 12.5, 86_400 + 3600);
+        assert_eq!(bars.len(), 2);
+        assert_eq!(
+            (bars[1].close, bars[1].high, bars[1].low),
+            (12.5, 12.5, 11.0)
+        );
+        update(&mut bars, 9.0, 2 * 86_400 + 60);
+        assert_eq!(bars.len(), 3);
+        assert_eq!(bars[2].close, 9.0);
+        assert_eq!(
+            Column::Sma(3).latest(&bars),
+            Some((10.0 + 12.5 + 9.0) / 3.0)
+        );
+    }
+
+    #[test]
+    fn column_names_round_trip() {
+        for name in [
+            "sma50",
+            "ema20",
+            "rsi14",
+            "macd",
+            "macdsignal",
+            "macdhist",
+            "bbupper20",
+            "bblower20",
+            "atr14",
+        ] {
+            assert_eq!(
+                Column::from_name(name).map(|column| column.name()),
+                Some(name.to_string())
+            );
+        }
+        assert_eq!(Column::from_name("RSI"), Some(Column::Rsi(14)));
+        assert_eq!(Column::from_name("sma0"), None);
+        assert_eq!(Column::from_name("macd5"), None);
+        assert_eq!(Column::from_name("vwap"), None);
+    }
+}
@@ -1,39 +1,66 @@
 #![forbid(unsafe_code)]
 
-use confy;
 use crossterm::{
     event::{poll, read, Event, KeyCode},
-    style::Color,
+    style::{Attribute, Color},
 };
-use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, time::Duration, vec};
+use std::{collections::HashMap, sync::OnceLock, time::Duration, vec};
 
+use chrono::{Datelike, TimeZone};
+use config::Config;
+use indicators::Column;
+
+mod chart;
+pub mod cli;
+mod completions;
+mod config;
 mod format;
-mod output;
+pub mod indicators;
+pub mod output;
+mod picker;
+mod risk;
+mod theme;
+mod transfer;
 mod yahoo;
 
-///zigfi configuration structure
-#[derive(Serialize, Deserialize, Clone)]
-pub struct Config {
-    greenisup: bool,
-    watchlists: HashMap<String, Vec<String>>,
+///Ways a watchlist can be displayed on the terminal
+#[derive(Clone, Copy, PartialEq)]
+pub enum View {
+    Table,
+    Plain,
+    Heatmap,
 }
 
-///Required for Config structs in confy crate
-impl ::std::default::Default for Config {
-    fn default() -> Self {
-        Self {
-            greenisup: true,
-            watchlists: HashMap::new(),
-        }
-    }
+///Order of rows when showing a watchlist
+#[derive(Clone, Copy, PartialEq)]
+pub enum Order {
+    Stored,
+    Symbol,
+    Change,
+}
+
+///Uses provided configuration file or profile instead of the default one
+pub fn use_config(file: Option<String>, profile: Option<String>) {
+    config::select(file, profile);
 }
 
 ///Sets up default configuration if not available
 pub fn startup() {
-    let mut cfg: Config = confy::load("zigfi").expect("Failed to load zigfi configuration.");
-    if cfg.watchlists.contains_key("default") {
-    } else {
+    let lock = config::lock();
+    let mut cfg = config::load();
+    let provider = cfg.provider();
+    if !config::PROVIDERS.contains(&provider.as_str()) {
+        panic!(
+            "Unknown provider \"{}\". Available: {}.",
+            provider,
+            config::PROVIDERS.join(", ")
+        );
+    }
+    //Warns before any screen is set up, then the default theme is used
+    if let Err(error) = active_palette(&cfg) {
+        eprintln!("{} Using the default theme.", error);
+    }
+    if !cfg.watchlists.contains_key("default") {
         cfg.watchlists.insert(
             "default".to_string(),
             vec![
@@ -43,18 +70,178 @@ pub fn startup() {
                 "SI=F".to_string(),
             ],
         );
-        confy::store("zigfi", cfg).expect("Failed to save new configuration.");
+        config::store(&cfg);
+    }
+    drop(lock);
+}
+
+///Returns decimals to display for a ticker, honoring the override in the configuration if set
+fn precision(cfg: &Config, ticker: &str, quote: f64, hint: usize) -> usize {
+    match cfg.precision.get(ticker) {
+        Some(decimals) => *decimals,
+        None => format::decimals(quote, hint),
+    }
+}
+
+///Returns locale set in the configuration or detected from the environment
+fn locale(cfg: &Config) -> format::Locale {
+    match &cfg.locale {
+        Some(tag) => format::Locale::from_tag(tag),
+        None => format::Locale::detect(),
+    }
+}
+
+///Returns colors of the active theme, with up and down swapped if colorswap is on, the default theme if it cannot be used
+fn palette(cfg: &Config) -> theme::Palette {
+    active_palette(cfg).unwrap_or_else(|_| {
+        let mut selected = theme::Theme::default();
+        if !cfg.greenisup {
+            selected = selected.swapped();
+        }
+        theme::Palette::new(&selected, theme::Depth::detect()).expect("Internal error.")
+    })
+}
+
+///Returns colors of the active theme, or why it cannot be used
+fn active_palette(cfg: &Config) -> Result<theme::Palette, String> {
+    let name = cfg.theme();
+    let mut selected = cfg
+        .themes
+        .get(&name)
+        .cloned()
+        .or_else(|| theme::Theme::builtin(&name))
+        .ok_or_else(|| format!("Theme \"{}\" does not exist.", name))?;
+    if !cfg.greenisup {
+        selected = selected.swapped();
+    }
+    theme::Palette::new(&selected, theme::Depth::detect())
+        .map_err(|error| format!("{} ({})", error, name))
+}
+
+///Returns direction indicator set in the configuration
+fn indicator(cfg: &Config) -> format::Indicator {
+    format::Indicator::from_name(&cfg.indicator)
+        .expect("Invalid \"indicator\" in zigfi configuration. Use none, arrows or signs.")
+}
+
+///Returns true if the latest trade is too old to be considered live
+fn is_stale(price: &yahoo::Price) -> bool {
+    chrono::Utc::now() - price.time > chrono::Duration::minutes(30)
+}
+
+///Requests prices of the watchlist's tickers in the provided order
+fn quotes(watchlist: &[String], interval: &str, order: Order) -> Vec<(String, yahoo::Price)> {
+    let (from, to) = format::get_time(interval);
+    let mut quotes: Vec<(String, yahoo::Price)> = watchlist
+        .iter()
+        .map(|ticker| (ticker.clone(), yahoo::get(ticker, from, to)))
+        .collect();
+    match order {
+        Order::Stored => {}
+        Order::Symbol => quotes.sort_by_key(|(ticker, _)| ticker.to_uppercase()),
+        Order::Change => quotes.sort_by(|a, b| b.1.difference.total_cmp(&a.1.difference)),
+    }
+    quotes
+}
+
+///Daily histories indicator columns are computed from, requested once per ticker and kept current with its latest quote
+struct Histories<'a> {
+    columns: &'a [Column],
+    bars: HashMap<String, Vec<yahoo_finance_api::Quote>>,
+}
+
+impl<'a> Histories<'a> {
+    fn new(columns: &'a [Column]) -> Self {
+        Histories {
+            columns,
+            bars: HashMap::new(),
+        }
+    }
+
+    ///Returns the latest value of every column, requesting the history only the first time
+    fn values(&mut self, ticker: &str, price: &yahoo::Price) -> Vec<Option<f64>> {
+        if self.columns.is_empty() {
+            return vec![];
+        }
+        let columns = self.columns;
+        let bars = self
+            .bars
+            .entry(ticker.to_string())
+            .or_insert_with(|| yahoo::history(ticker, "1d", indicators::range(columns)).bars);
+        indicators::update(bars, price.quote, price.time.timestamp() as u64);
+        columns.iter().map(|column| column.latest(bars)).collect()
+    }
+}
+
+///Formats indicator value with the price's decimals if it is in the ticker's currency, - if missing
+fn indicator_text(
+    column: &Column,
+    value: Option<f64>,
+    decimals: usize,
+    locale: &format::Locale,
+) -> String {
+    match value {
+        Some(value) if column.is_price() => format::price(value, decimals, locale),
+        Some(value) => format::price(value, 2, locale),
+        None => "-".to_string(),
+    }
+}
+
+///Formats indicator value without separators for machine-readable output
+fn indicator_plain(column: &Column, value: Option<f64>, decimals: usize) -> Option<String> {
+    let decimals = if column.is_price() { decimals } else { 2 };
+    value.map(|value| format::plain_price(value, decimals))
+}
+
+///Returns tickers of a saved watchlist
+pub fn watchlist(name: &str) -> Vec<String> {
+    match config::load().watchlists.remove(name) {
+        Some(tickers) => tickers,
+        None if cli::INTERVALS.contains(&name) => fail(&format!(
+            "Watchlist \"{0}\" does not exist. Use zigfi show default {0} for an interval.",
+            name
+        )),
+        None => fail(&format!("Watchlist \"{}\" does not exist.", name)),
+    }
+}
+
+///Exits with an error unless Yahoo Finance knows every ticker, for tickers that were never verified like quote's
+pub fn check_tickers(tickers: &[String]) {
+    let (from, now) = format::get_time("1d");
+    let mut unknown: Vec<&str> = vec![];
+    for ticker in tickers {
+        match yahoo::exists(ticker, from, now) {
+            yahoo::Lookup::Found => {}
+            yahoo::Lookup::Missing => unknown.push(ticker),
+            yahoo::Lookup::Failed(error) => fail(&format!(
+                "Could not check {} with Yahoo Finance: {}.",
+                ticker, error
+            )),
+        }
+    }
+    if !unknown.is_empty() {
+        fail(&format!("Unknown ticker/s: {}", unknown.join(", ")));
     }
 }
 
 ///Displays watchlist on the terminal
-pub fn display(query: &str, interval: &str) {
+pub fn display(
+    watchlist: &[String],
+    interval: &str,
+    view: View,
+    order: Order,
+    refresh: Option<Duration>,
+    columns: &[Column],
+) {
+    let cfg = config::load();
+    let refresh = refresh.unwrap_or_else(|| cfg.refresh());
+    if view == View::Heatmap {
+        return display_heatmap(&cfg, watchlist, interval, order, refresh);
+    }
+    if view == View::Plain || cfg.plain {
+        return display_plain(&cfg, watchlist, interval, order, columns);
+    }
     output::setup();
-    let cfg: Config = confy::load("zigfi").expect("Failed to load zigfi configuration.");
-    let watchlist = cfg
-        .watchlists
-        .get(query)
-        .expect("Failed to load watchlist.");
     if watchlist.is_empty() {
         output::write("Watchlist is empty. Press q to quit.");
         let mut event = read().expect("Terminal error.");
@@ -62,33 +249,106 @@ pub fn display(query: &str, interval: &str) {
             event = read().expect("Terminal error.");
         }
     } else {
-        let (from, to) = format::get_time(interval);
+        let locale = locale(&cfg);
+        let palette = palette(&cfg);
+        let indicator = indicator(&cfg);
+        let mut histories = Histories::new(columns);
         'outer: loop {
-            for ticker in watchlist.iter() {
-                let (quote, difference) = yahoo::get(ticker, from, to);
-                output::write_within_space(ticker, 10);
-                output::set_color(Color::Yellow);
-                output::write_within_space(quote.to_string().as_ref(), 20);
+            let quotes = quotes(watchlist, interval, order);
+            let prices: Vec<String> = quotes
+                .iter()
+                .map(|(ticker, price)| {
+                    let decimals = precision(&cfg, ticker, price.quote, price.hint);
+                    format::price(price.quote, decimals, &locale)
+                })
+                .collect();
+            //Long tickers and prices with many decimals widen their columns instead of running into the next
+            let ticker_end = quotes
+                .iter()
+                .map(|(ticker, _)| ticker.chars().count() as u16 + 2)
+                .max()
+                .unwrap_or(0)
+                .max(10);
+            let price_end = ticker_end
+                + prices
+                    .iter()
+                    .map(|price| price.chars().count() as u16 + 2)
+                    .max()
+                    .unwrap_or(0)
+                    .max(10);
+            let change_end = price_end + 14;
+            output::set_color(palette.header);
+            if cfg.emphasis {
+                output::set_attribute(Attribute::Bold);
+            }
+            output::write_within_space("Ticker", ticker_end);
+            output::write_within_space("Price", price_end);
+            if columns.is_empty() {
+                output::write("Change");
+            } else {
+                output::write_within_space("Change", change_end);
+                for (index, column) in columns.iter().enumerate() {
+                    output::write_within_space(
+                        &column.name().to_uppercase(),
+                        change_end + 14 + 14 * index as u16,
+                    );
+                }
+            }
+            output::reset_attribute();
+            output::reset_color();
+            output::skip_line();
+            for ((ticker, price), text) in quotes.iter().zip(&prices) {
+                let decimals = precision(&cfg, ticker, price.quote, price.hint);
+                output::write_within_space(ticker, ticker_end);
+                if is_stale(price) {
+                    output::set_color(palette.stale);
+                } else {
+                    output::set_color(palette.price);
+                }
+                output::write_within_space(text, price_end);
                 output::reset_color();
-                if difference.is_sign_positive() && cfg.greenisup {
-                    output::set_color(Color::Green);
-                } else if difference.is_sign_negative() && cfg.greenisup {
-                    output::set_color(Color::Red);
-                } else if difference.is_sign_positive() && !cfg.greenisup {
-                    output::set_color(Color::Red);
+                if cfg.gradient {
+                    output::set_color(palette.gradient(price.difference, cfg.gradient_range));
+                } else {
+                    output::set_color(palette.change(price.difference));
+                }
+                if cfg.emphasis && price.difference > 0.0 {
+                    output::set_attribute(Attribute::Bold);
+                } else if cfg.emphasis && price.difference < 0.0 {
+                    output::set_attribute(Attribute::Dim);
+                }
+                let change = format::change(price.difference, &locale, indicator);
+                if columns.is_empty() {
+                    output::write(&change);
+                    output::write("   ");
                 } else {
-                    output::set_color(Color::Green);
+                    output::write_within_space(&change, change_end);
                 }
-                output::write(difference.to_string().as_ref());
-                output::write("%");
-                output::write("   ");
+                output::reset_attribute();
                 output::reset_color();
+                for (index, (column, value)) in columns
+                    .iter()
+                    .zip(histories.values(ticker, price))
+                    .enumerate()
+                {
+                    output::write_within_space(
+                        &indicator_text(column, value, decimals, &locale),
+                        change_end + 14 + 14 * index as u16,
+                    );
+                }
                 output::skip_line();
             }
             output::skip_line();
+            output::write_then_nextline(
+                format!(
+                    "Updated {}",
+                    format::timestamp(chrono::Local::now(), &locale)
+                )
+                .as_ref(),
+            );
             output::write("Press q to quit.");
             output::reset_cursor();
-            if poll(Duration::from_millis(500)).expect("Terminal error.") {
+            if poll(refresh).expect("Terminal error.") {
                 let event = read().expect("Terminal error.");
                 if event == Event::Key(KeyCode::Char('q').into()) {
                     break 'outer;
@@ -98,245 +358,1449 @@ pub fn display(query: &str, interval: &str) {
     }
 }
 
-///Prints watchlist as text for piping
-pub fn print(query: &str, interval: &str) {
-    let cfg: Config = confy::load("zigfi").expect("Failed to load zigfi configuration.");
-    let watchlist = cfg
-        .watchlists
-        .get(query)
-        .expect("Failed to load watchlist.");
+///Displays watchlist as tiles colored by the size of each move, sized to fill the terminal
+fn display_heatmap(
+    cfg: &Config,
+    watchlist: &[String],
+    interval: &str,
+    order: Order,
+    refresh: Duration,
+) {
+    output::setup();
+    if watchlist.is_empty() {
+        output::write("Watchlist is empty. Press q to quit.");
+        let mut event = read().expect("Terminal error.");
+        while event != Event::Key(KeyCode::Char('q').into()) {
+            event = read().expect("Terminal error.");
+        }
+        return;
+    }
+    let locale = locale(cfg);
+    let palette = palette(cfg);
+    let indicator = indicator(cfg);
+    let mut last_size = (0, 0);
+    loop {
+        let quotes = quotes(watchlist, interval, order);
+        let (width, height) = output::size();
+        if (width, height) != last_size {
+            output::clear();
+            last_size = (width, height);
+        }
+        //Keeps two rows for the footer and aims for tiles about twice as wide as tall
+        let area = height.saturating_sub(2).max(1) as usize;
+        let count = quotes.len();
+        let columns = ((count as f64 * width as f64 / (2.0 * area as f64))
+            .sqrt()
+            .ceil() as usize)
+            .clamp(1, count);
+        let rows = count.div_ceil(columns);
+        let tile_width = (width as usize / columns).max(1);
+        let tile_height = (area / rows).max(1);
+        for (index, (ticker, price)) in quotes.iter().enumerate() {
+            let left = (index % columns * tile_width) as u16;
+            let top = (index / columns * tile_height) as u16;
+            let change = format::change(price.difference, &locale, indicator);
+            let single = format!("{} {}", ticker, change);
+            let lines = [ticker.as_str(), change.as_str()];
+            let middle = tile_height.saturating_sub(lines.len()) / 2;
+            let background = palette.gradient(price.difference, cfg.gradient_range);
+            output::set_background(background);
+            if background.is_some() {
+                output::set_color(Some(Color::Black));
+            }
+            for row in 0..tile_height {
+                let text = if tile_height == 1 {
+                    single.as_str()
+                } else {
+                    match row.checked_sub(middle) {
+                        Some(line) if line < lines.len() => lines[line],
+                        _ => "",
+                    }
+                };
+                let text: String = text.chars().take(tile_width).collect();
+                let padding = tile_width - text.chars().count();
+                output::move_to(left, top + row as u16);
+                output::write(
+                    format!(
+                        "{}{}{}",
+                        " ".repeat(padding / 2),
+                        text,
+                        " ".repeat(padding - padding / 2)
+                    )
+                    .as_ref(),
+                );
+            }
+            output::reset_background();
+            output::reset_color();
+        }
+        output::move_to(0, (rows * tile_height) as u16);
+        output::skip_line();
+        output::write(
+            format!(
+                "Updated {}. Press q to quit.",
+                format::timestamp(chrono::Local::now(), &locale)
+            )
+            .as_ref(),
+        );
+        if poll(refresh).expect("Terminal error.") {
+            let event = read().expect("Terminal error.");
+            if event == Event::Key(KeyCode::Char('q').into()) {
+                break;
+            }
+        }
+    }
+}
+
+///Displays watchlist as plain lines for screen readers, refreshing only when asked
+fn display_plain(
+    cfg: &Config,
+    watchlist: &[String],
+    interval: &str,
+    order: Order,
+    columns: &[Column],
+) {
+    if watchlist.is_empty() {
+        println!("Watchlist is empty.");
+        return;
+    }
+    let locale = locale(cfg);
+    let indicator = match indicator(cfg) {
+        format::Indicator::None => format::Indicator::Signs,
+        indicator => indicator,
+    };
+    let mut histories = Histories::new(columns);
+    loop {
+        for (ticker, price) in quotes(watchlist, interval, order).iter() {
+            let decimals = precision(cfg, ticker, price.quote, price.hint);
+            let stale = if is_stale(price) { " (delayed)" } else { "" };
+            let mut line = format!(
+                "{} {}{} {}",
+                ticker,
+                format::price(price.quote, decimals, &locale),
+                stale,
+                format::change(price.difference, &locale, indicator)
+            );
+            for (column, value) in columns.iter().zip(histories.values(ticker, price)) {
+                line.push_str(&format!(
+                    ", {} {}",
+                    column.name().to_uppercase(),
+                    indicator_text(column, value, decimals, &locale)
+                ));
+            }
+            println!("{}", line);
+        }
+        println!(
+            "Updated {}",
+            format::timestamp(chrono::Local::now(), &locale)
+        );
+        println!("Press Enter to refresh, or type q then Enter to quit.");
+        let mut line = String::new();
+        let read = std::io::stdin()
+            .read_line(&mut line)
+            .expect("Terminal error.");
+        if read == 0 || line.trim().eq_ignore_ascii_case("q") {
+            break;
+        }
+    }
+}
+
+///Prints watchlist as text for piping, again after every watch period if provided
+pub fn print(
+    watchlist: &[String],
+    interval: &str,
+    order: Order,
+    watch: Option<Duration>,
+    columns: &[Column],
+) {
+    let cfg = config::load();
     if watchlist.is_empty() {
         panic!("Watchlist empty");
-    } else {
-        let (from, to) = format::get_time(interval);
-        for ticker in watchlist.iter() {
-            let (quote, difference) = yahoo::get(ticker, from, to);
-            println!(
+    }
+    let locale = locale(&cfg);
+    let mut histories = Histories::new(columns);
+    loop {
+        for (ticker, price) in quotes(watchlist, interval, order).iter() {
+            let decimals = precision(&cfg, ticker, price.quote, price.hint);
+            let mut line = format!(
                 "{} {} {}%",
                 ticker,
-                quote.to_string(),
-                difference.to_string()
+                format::price(price.quote, decimals, &locale),
+                format::percent(price.difference, &locale)
             );
+            for (column, value) in columns.iter().zip(histories.values(ticker, price)) {
+                line.push(' ');
+                line.push_str(&indicator_text(column, value, decimals, &locale));
+            }
+            println!("{}", line);
+        }
+        match watch {
+            Some(period) => std::thread::sleep(period),
+            None => break,
         }
     }
 }
 
-///Prints watchlist as json for piping
-pub fn print_json(query: &str, interval: &str) {
-    let cfg: Config = confy::load("zigfi").expect("Failed to load zigfi configuration.");
-    let watchlist = cfg
-        .watchlists
-        .get(query)
-        .expect("Failed to load watchlist.");
+///Prints watchlist as json for piping, again after every watch period if provided
+pub fn print_json(
+    watchlist: &[String],
+    interval: &str,
+    order: Order,
+    watch: Option<Duration>,
+    columns: &[Column],
+) {
+    let cfg = config::load();
     if watchlist.is_empty() {
         panic!("Watchlist empty");
-    } else {
-        let (from, to) = format::get_time(interval);
-        for ticker in watchlist.iter() {
-            let (quote, difference) = yahoo::get(ticker, from, to);
+    }
+    let mut histories = Histories::new(columns);
+    loop {
+        for (ticker, price) in quotes(watchlist, interval, order).iter() {
+            let decimals = precision(&cfg, ticker, price.quote, price.hint);
+            let mut values = String::new();
+            for (column, value) in columns.iter().zip(histories.values(ticker, price)) {
+                values.push_str(&format!(
+                    ",\"{}\":{}",
+                    column.name(),
+                    indicator_plain(column, value, decimals).unwrap_or_else(|| "null".to_string())
+                ));
+            }
             println!(
-                "{{\"ticker\":\"{}\",\"price\":{},\"difference\":{}%}}",
+                "{{\"ticker\":\"{}\",\"price\":{},\"difference\":{}{}}}",
                 ticker,
-                quote.to_string(),
-                difference.to_string()
+                format::plain_price(price.quote, decimals),
+                format::plain_percent(price.difference),
+                values
             );
         }
+        match watch {
+            Some(period) => std::thread::sleep(period),
+            None => break,
+        }
     }
 }
 
-///Shows search results for provided query
-pub fn search(query: &str) {
-    output::setup();
-    let resp = yahoo::search(query);
-    let mut captured = false;
+///Prints watchlist as csv for piping, again after every watch period if provided
+pub fn print_csv(
+    watchlist: &[String],
+    interval: &str,
+    order: Order,
+    watch: Option<Duration>,
+    columns: &[Column],
+) {
+    let cfg = config::load();
+    if watchlist.is_empty() {
+        panic!("Watchlist empty");
+    }
+    let mut header = "ticker,price,change".to_string();
+    for column in columns {
+        header.push(',');
+        header.push_str(&column.name());
+    }
+    println!("{}", header);
+    let mut histories = Histories::new(columns);
     loop {
-        if captured == false {
-            for item in &resp.quotes {
-                output::write_within_space(item.symbol.as_ref(), 18);
-                output::write_then_nextline(item.short_name.as_ref());
+        for (ticker, price) in quotes(watchlist, interval, order).iter() {
+            let decimals = precision(&cfg, ticker, price.quote, price.hint);
+            let mut line = format!(
+                "{},{},{}",
+                ticker,
+                format::plain_price(price.quote, decimals),
+                format::plain_percent(price.difference)
+            );
+            for (column, value) in columns.iter().zip(histories.values(ticker, price)) {
+                line.push(',');
+                line.push_str(&indicator_plain(column, value, decimals).unwrap_or_default());
             }
-            captured = true;
+            println!("{}", line);
         }
-        output::skip_line();
-        output::write("Search results displayed. Press q to quit.");
-        let event = read().expect("Terminal error.");
-        if event == Event::Key(KeyCode::Char('q').into()) {
-            break;
+        match watch {
+            Some(period) => std::thread::sleep(period),
+            None => break,
         }
     }
 }
 
-///Creates a new watchlist
-pub fn new(watchlist: &str, tickers: Vec<String>) {
-    output::setup();
-    let cfg: Config = confy::load("zigfi").expect("Failed to load zigfi configuration.");
-    if cfg.watchlists.contains_key(watchlist) {
-        output::write_then_nextline("Watchlist provided already exist and will be overwritten.");
-        output::write_then_nextline("Do you wish to continue? (y/n)");
-        loop {
-            let event = read().expect("Terminal error.");
-            if event == Event::Key(KeyCode::Char('y').into()) {
-                new_continuation(cfg, watchlist, tickers);
-                break;
-            } else if event == Event::Key(KeyCode::Char('n').into()) {
-                break;
+///Quote types Yahoo Finance reports for the names search filters accept
+const QUOTE_TYPES: [(&str, &str); 7] = [
+    ("equity", "EQUITY"),
+    ("etf", "ETF"),
+    ("crypto", "CRYPTOCURRENCY"),
+    ("future", "FUTURE"),
+    ("currency", "CURRENCY"),
+    ("index", "INDEX"),
+    ("mutualfund", "MUTUALFUND"),
+];
+
+///Keeps search results of a quote type and exchange, when provided
+fn filter(
+    quotes: Vec<yahoo_finance_api::YQuoteItem>,
+    kind: Option<&str>,
+    exchange: Option<&str>,
+) -> Vec<yahoo_finance_api::YQuoteItem> {
+    let kind = kind.map(|kind| {
+        QUOTE_TYPES
+            .iter()
+            .find(|(name, _)| *name == kind)
+            .map_or(kind, |(_, yahoo)| *yahoo)
+    });
+    quotes
+        .into_iter()
+        .filter(|item| kind.is_none_or(|kind| item.quote_type.eq_ignore_ascii_case(kind)))
+        .filter(|item| exchange.is_none_or(|exchange| item.exchange.eq_ignore_ascii_case(exchange)))
+        .collect()
+}
+
+///Shows search results for provided query, optionally only of a quote type or exchange
+pub fn search(query: &str, format: &str, kind: Option<&str>, exchange: Option<&str>) {
+    if format == "table" {
+        return picker::run(query, kind, exchange);
+    }
+    let results = filter(yahoo::search(query).quotes, kind, exchange);
+    match format {
+        "json" => {
+            for item in results {
+                println!(
+                    "{}",
+                    serde_json::json!({
+                        "symbol": item.symbol,
+                        "name": item.short_name,
+                        "exchange": item.exchange,
+                        "type": item.quote_type,
+                        "score": item.score,
+                    })
+                );
+            }
+        }
+        "csv" => {
+            println!("symbol,name,exchange,type,score");
+            for item in results {
+                println!(
+                    "{},{},{},{},{}",
+                    transfer::csv_field(&item.symbol),
+                    transfer::csv_field(&item.short_name),
+                    transfer::csv_field(&item.exchange),
+                    transfer::csv_field(&item.quote_type),
+                    item.score
+                );
+            }
+        }
+        _ => {
+            for item in results {
+                println!(
+                    "{} {} {} {}",
+                    item.symbol, item.exchange, item.quote_type, item.short_name
+                );
+            }
+        }
+    }
+}
+
+///Periods of the performance view, as named in its header
+const PERIODS: [&str; 7] = ["1D", "1W", "1M", "3M", "YTD", "1Y", "5Y"];
+
+///Returns percentage return of a ticker over every performance period from one daily history
+fn performance(ticker: &str) -> Vec<Option<f64>> {
+    let bars = yahoo::history(ticker, "1d", "5y").bars;
+    let date = |bar: &yahoo_finance_api::Quote| {
+        chrono::Utc
+            .timestamp_opt(bar.timestamp as i64, 0)
+            .single()
+            .expect("Failed to process Yahoo Finance Response.")
+            .naive_utc()
+            .date()
+    };
+    let (last, first) = match (bars.last(), bars.first()) {
+        (Some(last), Some(first)) => (last, first),
+        _ => return vec![None; PERIODS.len()],
+    };
+    let today = date(last);
+    //Closes on the last trading day on or before the target, the range may start a few days after it
+    let close_on = |target: chrono::NaiveDate| {
+        bars.iter()
+            .rev()
+            .find(|bar| date(bar) <= target)
+            .or_else(|| (date(first) - target <= chrono::Duration::days(4)).then_some(first))
+            .map(|bar| bar.adjclose)
+    };
+    let bases = [
+        bars.len()
+            .checked_sub(2)
+            .map(|previous| bars[previous].adjclose),
+        close_on(today - chrono::Duration::days(7)),
+        close_on(format::months_back(today, 1)),
+        close_on(format::months_back(today, 3)),
+        close_on(chrono::NaiveDate::from_ymd_opt(today.year() - 1, 12, 31).expect("Invalid date.")),
+        close_on(format::months_back(today, 12)),
+        close_on(format::months_back(today, 60)),
+    ];
+    bases
+        .iter()
+        .map(|base| base.map(|base| format::growth(base, last.adjclose)))
+        .collect()
+}
+
+///Shows returns of every ticker over several periods, on the terminal or printed as text, json or csv
+pub fn display_performance(watchlist: &[String], format: &str) {
+    let cfg = config::load();
+    if watchlist.is_empty() {
+        fail("Watchlist is empty.");
+    }
+    let locale = locale(&cfg);
+    //Histories are requested once, five years of bars are too many to download on every refresh
+    let rows: Vec<(&String, Vec<Option<f64>>)> = watchlist
+        .iter()
+        .map(|ticker| (ticker, performance(ticker)))
+        .collect();
+    match format {
+        "json" => {
+            for (ticker, returns) in rows {
+                let mut line = format!("{{\"ticker\":\"{}\"", ticker);
+                for (period, value) in PERIODS.iter().zip(returns) {
+                    line.push_str(&format!(
+                        ",\"{}\":{}",
+                        period.to_lowercase(),
+                        value.map_or("null".to_string(), format::plain_percent)
+                    ));
+                }
+                println!("{}}}", line);
             }
         }
+        "csv" => {
+            println!("ticker,{}", PERIODS.join(",").to_lowercase());
+            for (ticker, returns) in rows {
+                let values: Vec<String> = returns
+                    .into_iter()
+                    .map(|value| value.map(format::plain_percent).unwrap_or_default())
+                    .collect();
+                println!("{},{}", ticker, values.join(","));
+            }
+        }
+        "text" => {
+            for (ticker, returns) in rows {
+                let mut line = ticker.to_string();
+                for (period, value) in PERIODS.iter().zip(returns) {
+                    line.push_str(&format!(
+                        " {} {}",
+                        period,
+                        value.map_or("-".to_string(), |value| format!(
+                            "{}%",
+                            format::percent(value, &locale)
+                        ))
+                    ));
+                }
+                println!("{}", line);
+            }
+        }
+        _ => {
+            let palette = palette(&cfg);
+            let indicator = indicator(&cfg);
+            output::setup();
+            output::set_color(palette.header);
+            if cfg.emphasis {
+                output::set_attribute(Attribute::Bold);
+            }
+            let ticker_end = rows
+                .iter()
+                .map(|(ticker, _)| ticker.chars().count() as u16 + 2)
+                .max()
+                .unwrap_or(0)
+                .max(10);
+            output::write_within_space("Ticker", ticker_end);
+            for (index, period) in PERIODS.iter().enumerate() {
+                output::write_within_space(period, ticker_end + 12 + 12 * index as u16);
+            }
+            output::reset_attribute();
+            output::reset_color();
+            output::skip_line();
+            for (ticker, returns) in rows {
+                output::write_within_space(ticker, ticker_end);
+                for (index, value) in returns.into_iter().enumerate() {
+                    let text = match value {
+                        Some(value) => {
+                            if cfg.gradient {
+                                output::set_color(palette.gradient(value, cfg.gradient_range));
+                            } else {
+                                output::set_color(palette.change(value));
+                            }
+                            format::change(value, &locale, indicator)
+                        }
+                        None => "-".to_string(),
+                    };
+                    output::write_within_space(&text, ticker_end + 12 + 12 * index as u16);
+                    output::reset_color();
+                }
+                output::skip_line();
+            }
+            output::skip_line();
+            output::write_then_nextline(
+                format!(
+                    "Updated {}",
+                    format::timestamp(chrono::Local::now(), &locale)
+                )
+                .as_ref(),
+            );
+            output::write("Press q to quit.");
+            let mut event = read().expect("Terminal error.");
+            while event != Event::Key(KeyCode::Char('q').into()) {
+                event = read().expect("Terminal error.");
+            }
+        }
+    }
+}
+
+///Compares tickers over a range with every series starting from 100, on a chart with optional indicator overlays or printed
+pub fn compare(tickers: &[String], range: &str, format: &str, columns: &[Column]) {
+    let cfg = config::load();
+    if let Some(column) = columns.iter().find(|column| !column.is_overlay()) {
+        fail(&format!(
+            "{} cannot be drawn over prices. Use sma, ema, bbupper or bblower.",
+            column.name()
+        ));
+    }
+    let locale = locale(&cfg);
+    //Keeps the chart to a few hundred points whatever the range
+    let bar = match range {
+        "1d" | "5d" => "1h",
+        "5y" | "10y" | "max" => "1wk",
+        _ => "1d",
+    };
+    let mut series = vec![];
+    let mut overlays = vec![];
+    let mut overlay_legend = vec![];
+    let mut rows = vec![];
+    for (index, ticker) in tickers.iter().enumerate() {
+        let bars = yahoo::history(ticker, bar, range).bars;
+        let first = match bars.first() {
+            Some(first) if first.adjclose == 0.0 => {
+                fail(&format!("History of {} starts at zero.", ticker))
+            }
+            Some(first) => first.adjclose,
+            None => fail(&format!("No history for {}.", ticker)),
+        };
+        let points: Vec<(u64, f64)> = bars
+            .iter()
+            .map(|bar| (bar.timestamp, bar.adjclose / first * 100.0))
+            .collect();
+        let values: Vec<f64> = points.iter().map(|(_, value)| *value).collect();
+        let times: Vec<u64> = points.iter().map(|(time, _)| *time).collect();
+        let volatility = risk::bars_per_year(&times)
+            .and_then(|per_year| risk::volatility(&risk::returns(&values), per_year));
+        let last = values.last().copied().unwrap_or(100.0);
+        rows.push((
+            ticker.clone(),
+            last,
+            last - 100.0,
+            volatility,
+            risk::max_drawdown(&values),
+        ));
+        series.push(points);
+        //Overlays use adjusted closes so they start from 100 with the ticker
+        let adjusted: Vec<yahoo_finance_api::Quote> = bars
+            .iter()
+            .map(|bar| yahoo_finance_api::Quote {
+                close: bar.adjclose,
+                ..bar.clone()
+            })
+            .collect();
+        for column in columns {
+            let points = column
+                .series(&adjusted)
+                .into_iter()
+                .zip(&bars)
+                .filter_map(|(value, bar)| Some((bar.timestamp, value? / first * 100.0)))
+                .collect();
+            overlays.push((index, points));
+            overlay_legend.push(format!("{} {}", ticker, column.name().to_uppercase()));
+        }
+    }
+    match format {
+        "json" => {
+            for (ticker, last, total, volatility, drawdown) in rows {
+                println!(
+                    "{{\"ticker\":\"{}\",\"last\":{},\"return\":{},\"volatility\":{},\"drawdown\":{}}}",
+                    ticker,
+                    format::plain_price(last, 2),
+                    format::plain_percent(total),
+                    volatility.map_or("null".to_string(), format::plain_percent),
+                    format::plain_percent(drawdown)
+                );
+            }
+        }
+        "csv" => {
+            println!("ticker,last,return,volatility,drawdown");
+            for (ticker, last, total, volatility, drawdown) in rows {
+                println!(
+                    "{},{},{},{},{}",
+                    ticker,
+                    format::plain_price(last, 2),
+                    format::plain_percent(total),
+                    volatility.map(format::plain_percent).unwrap_or_default(),
+                    format::plain_percent(drawdown)
+                );
+            }
+        }
+        _ => {
+            let header = format!(
+                "{:<10}{:>10}{:>12}{:>14}{:>16}",
+                "Ticker", "Last", "Return", "Volatility", "Max drawdown"
+            );
+            let legend: Vec<String> = rows
+                .into_iter()
+                .map(|(ticker, last, total, volatility, drawdown)| {
+                    format!(
+                        "{:<10}{:>10}{:>12}{:>14}{:>16}",
+                        ticker,
+                        format::price(last, 2, &locale),
+                        format!("{}%", format::percent(total, &locale)),
+                        volatility.map_or("-".to_string(), |volatility| format!(
+                            "{}%",
+                            format::percent(volatility, &locale)
+                        )),
+                        format!("{}%", format::percent(drawdown, &locale))
+                    )
+                })
+                .collect();
+            if format == "chart" {
+                let mut legend = legend;
+                legend.extend(overlay_legend);
+                return chart::run(&series, &overlays, &header, &legend, &palette(&cfg));
+            }
+            println!("{}", header);
+            for line in legend {
+                println!("{}", line);
+            }
+        }
+    }
+}
+
+///Prints every bar of a ticker's history as an aligned table, csv or json lines
+pub fn history(ticker: &str, range: &str, bar: &str, format: &str) {
+    let cfg = config::load();
+    let history = yahoo::history(ticker, bar, range);
+    let decimals = precision(
+        &cfg,
+        ticker,
+        history.bars.last().map_or(0.0, |last| last.close),
+        history.hint,
+    );
+    //Hourly bars need the time, the rest only the date
+    let pattern = if bar == "1h" {
+        "%Y-%m-%dT%H:%M:%SZ"
     } else {
-        new_continuation(cfg, watchlist, tickers);
+        "%Y-%m-%d"
+    };
+    let rows: Vec<(String, [f64; 5], u64)> = history
+        .bars
+        .iter()
+        .map(|quote| {
+            (
+                chrono::Utc
+                    .timestamp_opt(quote.timestamp as i64, 0)
+                    .single()
+                    .expect("Failed to process Yahoo Finance Response.")
+                    .format(pattern)
+                    .to_string(),
+                [
+                    quote.open,
+                    quote.high,
+                    quote.low,
+                    quote.close,
+                    quote.adjclose,
+                ],
+                quote.volume,
+            )
+        })
+        .collect();
+    match format {
+        "json" => {
+            for (time, [open, high, low, close, adjclose], volume) in rows {
+                println!(
+                    "{{\"time\":\"{}\",\"open\":{},\"high\":{},\"low\":{},\"close\":{},\"adjclose\":{},\"volume\":{}}}",
+                    time,
+                    format::plain_price(open, decimals),
+                    format::plain_price(high, decimals),
+                    format::plain_price(low, decimals),
+                    format::plain_price(close, decimals),
+                    format::plain_price(adjclose, decimals),
+                    volume
+                );
+            }
+        }
+        "csv" => {
+            println!("time,open,high,low,close,adjclose,volume");
+            for (time, prices, volume) in rows {
+                let prices: Vec<String> = prices
+                    .iter()
+                    .map(|price| format::plain_price(*price, decimals))
+                    .collect();
+                println!("{},{},{}", time, prices.join(","), volume);
+            }
+        }
+        _ => {
+            let locale = locale(&cfg);
+            let table: Vec<Vec<String>> = rows
+                .into_iter()
+                .map(|(time, prices, volume)| {
+                    let mut row = vec![time];
+                    row.extend(
+                        prices
+                            .iter()
+                            .map(|price| format::price(*price, decimals, &locale)),
+                    );
+                    row.push(format::price(volume as f64, 0, &locale));
+                    row
+                })
+                .collect();
+            let header = [
+                "Time",
+                "Open",
+                "High",
+                "Low",
+                "Close",
+                "Adj Close",
+                "Volume",
+            ];
+            print_table(&header, &table);
+        }
     }
 }
 
-///Continues creating new watchlist once duplicate has been cleared if there'll be at new()
-fn new_continuation(mut cfg: Config, watchlist: &str, tickers: Vec<String>) {
-    let (from, now) = format::get_time("1d");
-    loop {
-        let mut verified_tickers: Vec<String> = vec![];
-        for ticker in tickers.iter() {
-            let _ = yahoo::get(ticker, from, now);
-            verified_tickers.push(ticker.to_string());
-        }
-        if verified_tickers.is_empty() {
-            cfg.watchlists.insert(watchlist.to_string(), vec![]);
-            confy::store("zigfi", cfg).expect("Failed to save.");
-            output::write_then_nextline("Empty watchlist created");
-            output::write("Press q to quit...");
-        } else {
-            output::write_then_nextline("Watchlist created");
-            output::write("Press q to quit...");
-            cfg.watchlists
-                .insert(watchlist.to_string(), verified_tickers);
-            confy::store("zigfi", cfg).expect("Terminal error.");
+///Prints rows under a header in aligned columns, the first one left-aligned and the rest right-aligned
+fn print_table(header: &[&str], rows: &[Vec<String>]) {
+    let widths: Vec<usize> = header
+        .iter()
+        .enumerate()
+        .map(|(index, title)| {
+            rows.iter()
+                .map(|row| row[index].chars().count())
+                .fold(title.chars().count(), usize::max)
+        })
+        .collect();
+    let line = |cells: Vec<&str>| {
+        let cells: Vec<String> = cells
+            .iter()
+            .zip(&widths)
+            .enumerate()
+            .map(|(index, (cell, width))| match index {
+                0 => format!("{:<1$}", cell, width),
+                _ => format!("{:>1$}", cell, width),
+            })
+            .collect();
+        println!("{}", cells.join("  "));
+    };
+    line(header.to_vec());
+    for row in rows {
+        line(row.iter().map(String::as_str).collect());
+    }
+}
+
+///Returns daily adjusted closes of a ticker over a range, at the exchange's local time of every bar
+fn daily(ticker: &str, range: &str) -> Vec<(u64, f64)> {
+    let history = yahoo::history(ticker, "1d", range);
+    history
+        .bars
+        .iter()
+        .map(|bar| ((bar.timestamp as i64 + history.offset) as u64, bar.adjclose))
+        .collect()
+}
+
+///Prints volatility, drawdown, Sharpe ratio and beta against a benchmark of every ticker from daily history
+pub fn risk(watchlist: &[String], benchmark: Option<&str>, range: &str, format: &str) {
+    let cfg = config::load();
+    if watchlist.is_empty() {
+        fail("Watchlist is empty.");
+    }
+    let benchmark = benchmark.unwrap_or(&cfg.benchmark).to_string();
+    let against = daily(&benchmark, range);
+    let rows: Vec<(String, [Option<f64>; 4])> = watchlist
+        .iter()
+        .map(|ticker| {
+            let closes = daily(ticker, range);
+            let values: Vec<f64> = closes.iter().map(|(_, close)| *close).collect();
+            let times: Vec<u64> = closes.iter().map(|(time, _)| *time).collect();
+            let returns = risk::returns(&values);
+            let per_year = risk::bars_per_year(&times);
+            let (own, theirs) = risk::align(&closes, &against);
+            (
+                ticker.clone(),
+                [
+                    per_year.and_then(|per_year| risk::volatility(&returns, per_year)),
+                    (!values.is_empty()).then(|| risk::max_drawdown(&values)),
+                    per_year
+                        .and_then(|per_year| risk::sharpe(&returns, per_year, cfg.risk_free_rate)),
+                    risk::beta(&risk::returns(&own), &risk::returns(&theirs)),
+                ],
+            )
+        })
+        .collect();
+    let beta = format!("beta_{}", benchmark.to_lowercase());
+    let keys = ["volatility", "drawdown", "sharpe", beta.as_str()];
+    //Volatility and drawdown are percentages, Sharpe and beta are ratios
+    let plain = |index: usize, value: f64| match index {
+        0 | 1 => format::plain_percent(value),
+        _ => format::plain_price(value, 2),
+    };
+    match format {
+        "json" => {
+            for (ticker, values) in rows {
+                let mut line = format!("{{\"ticker\":\"{}\"", ticker);
+                for (index, (key, value)) in keys.iter().zip(values).enumerate() {
+                    line.push_str(&format!(
+                        ",\"{}\":{}",
+                        key,
+                        value.map_or("null".to_string(), |value| plain(index, value))
+                    ));
+                }
+                println!("{}}}", line);
+            }
         }
-        let mut event = read().expect("Terminal error.");
-        while event != Event::Key(KeyCode::Char('q').into()) {
-            event = read().expect("Terminal error.");
+        "csv" => {
+            println!("ticker,{}", keys.join(","));
+            for (ticker, values) in rows {
+                let values: Vec<String> = values
+                    .iter()
+                    .enumerate()
+                    .map(|(index, value)| {
+                        value.map(|value| plain(index, value)).unwrap_or_default()
+                    })
+                    .collect();
+                println!("{},{}", ticker, values.join(","));
+            }
+        }
+        _ => {
+            let locale = locale(&cfg);
+            let table: Vec<Vec<String>> = rows
+                .into_iter()
+                .map(|(ticker, values)| {
+                    let mut row = vec![ticker];
+                    row.extend(values.iter().enumerate().map(|(index, value)| {
+                        match (index, value) {
+                            (_, None) => "-".to_string(),
+                            (0 | 1, Some(value)) => {
+                                format!("{}%", format::percent(*value, &locale))
+                            }
+                            (_, Some(value)) => format::price(*value, 2, &locale),
+                        }
+                    }));
+                    row
+                })
+                .collect();
+            let beta = format!("Beta ({})", benchmark);
+            print_table(
+                &[
+                    "Ticker",
+                    "Volatility",
+                    "Max drawdown",
+                    "Sharpe",
+                    beta.as_str(),
+                ],
+                &table,
+            );
+            println!();
+            println!(
+                "Range {}, Sharpe with a {}% risk-free rate (set risk_free_rate in the configuration).",
+                range,
+                format::percent(cfg.risk_free_rate, &locale)
+            );
         }
-        break;
+    }
+}
+
+///Prints correlation of daily returns between every pair of tickers, to spot lists that move as one
+pub fn correlation(watchlist: &[String], range: &str, format: &str) {
+    let cfg = config::load();
+    if watchlist.len() < 2 {
+        fail("Correlation needs at least two tickers.");
+    }
+    let closes: Vec<Vec<(u64, f64)>> = watchlist
+        .iter()
+        .map(|ticker| daily(ticker, range))
+        .collect();
+    let matrix: Vec<Vec<Option<f64>>> = closes
+        .iter()
+        .map(|first| {
+            closes
+                .iter()
+                .map(|second| {
+                    let (first, second) = risk::align(first, second);
+                    risk::correlation(&risk::returns(&first), &risk::returns(&second))
+                })
+                .collect()
+        })
+        .collect();
+    match format {
+        "json" => {
+            for (ticker, values) in watchlist.iter().zip(&matrix) {
+                let mut line = format!("{{\"ticker\":\"{}\"", ticker);
+                for (other, value) in watchlist.iter().zip(values) {
+                    line.push_str(&format!(
+                        ",\"{}\":{}",
+                        other,
+                        value.map_or("null".to_string(), |value| format::plain_price(value, 2))
+                    ));
+                }
+                println!("{}}}", line);
+            }
+        }
+        "csv" => {
+            println!("ticker,{}", watchlist.join(","));
+            for (ticker, values) in watchlist.iter().zip(&matrix) {
+                let values: Vec<String> = values
+                    .iter()
+                    .map(|value| {
+                        value
+                            .map(|value| format::plain_price(value, 2))
+                            .unwrap_or_default()
+                    })
+                    .collect();
+                println!("{},{}", ticker, values.join(","));
+            }
+        }
+        _ => {
+            let locale = locale(&cfg);
+            let mut header = vec![""];
+            header.extend(watchlist.iter().map(String::as_str));
+            let table: Vec<Vec<String>> = watchlist
+                .iter()
+                .zip(&matrix)
+                .map(|(ticker, values)| {
+                    let mut row = vec![ticker.clone()];
+                    row.extend(values.iter().map(|value| {
+                        value.map_or("-".to_string(), |value| format::price(value, 2, &locale))
+                    }));
+                    row
+                })
+                .collect();
+            print_table(&header, &table);
+            let mut pairs: Vec<(usize, usize, f64)> = vec![];
+            for (row, values) in matrix.iter().enumerate() {
+                for (column, value) in values.iter().enumerate().skip(row + 1) {
+                    if let Some(value) = value {
+                        pairs.push((row, column, *value));
+                    }
+                }
+            }
+            if let Some(average) =
+                risk::mean(&pairs.iter().map(|(_, _, value)| *value).collect::<Vec<_>>())
+            {
+                println!();
+                println!("Average correlation {}", format::price(average, 2, &locale));
+                if let Some((row, column, value)) = pairs.iter().max_by(|a, b| a.2.total_cmp(&b.2))
+                {
+                    println!(
+                        "Most correlated {} and {} at {}",
+                        watchlist[*row],
+                        watchlist[*column],
+                        format::price(*value, 2, &locale)
+                    );
+                }
+            }
+        }
+    }
+}
+
+///Creates a new watchlist
+pub fn new(watchlist: &str, tickers: Vec<String>) {
+    begin();
+    let cfg = config::load();
+    if cfg.watchlists.contains_key(watchlist)
+        && !confirm("Watchlist provided already exist and will be overwritten.")
+    {
+        declined();
+        return;
+    }
+    let verified_tickers = match verify(&tickers) {
+        Some(verified_tickers) => verified_tickers,
+        None => return,
+    };
+    let lock = config::lock();
+    let mut cfg = config::load();
+    let created = verified_tickers.len();
+    cfg.watchlists
+        .insert(watchlist.to_string(), verified_tickers);
+    config::store(&cfg);
+    drop(lock);
+    if created == 0 {
+        finish_partial("Empty watchlist created", created < tickers.len());
+    } else {
+        finish_partial(
+            format!("Watchlist created with {} ticker/s", created).as_ref(),
+            created < tickers.len(),
+        );
     }
 }
 
 ///Adds ticker/s to watchlist
 pub fn add(watchlist: &str, tickers: Vec<String>) {
-    output::setup();
-    let mut cfg: Config = confy::load("zigfi").expect("Failed to load zigfi configuration.");
-    let (from, now) = format::get_time("1d");
-    let mut verified_tickers: Vec<String> = vec![];
-    for ticker in tickers.iter() {
-        let _ = yahoo::get(ticker, from, now);
-        verified_tickers.push(ticker.to_string());
+    begin();
+    let cfg = config::load();
+    if !cfg.watchlists.contains_key(watchlist) {
+        finish_with_error(&cfg, "Watchlist does not exist.");
+        return;
     }
+    let verified_tickers = match verify(&tickers) {
+        Some(verified_tickers) => verified_tickers,
+        None => return,
+    };
+    let lock = config::lock();
+    let mut cfg = config::load();
     let mut clone = cfg
         .watchlists
         .get(watchlist)
         .expect("Internal error.")
         .clone();
+    let added = verified_tickers.len();
     for ticker in verified_tickers {
         clone.push(ticker);
     }
     cfg.watchlists.insert(watchlist.to_string(), clone);
-    confy::store("zigfi", cfg).expect("Failed to save.");
-    output::write_then_nextline("Ticker/s has been added to the watchlist.");
-    output::write("Press q to quit...");
-    let mut event = read().expect("Terminal error.");
-    while event != Event::Key(KeyCode::Char('q').into()) {
-        event = read().expect("Terminal error.");
+    config::store(&cfg);
+    drop(lock);
+    if added > 0 {
+        finish_partial(
+            format!("{} ticker/s has been added to the watchlist.", added).as_ref(),
+            added < tickers.len(),
+        );
+    } else {
+        finish_partial(
+            "No ticker was added to the watchlist.",
+            added < tickers.len(),
+        );
+    }
+}
+
+///Collects tickers from the command line, "-" reading them from stdin, and from a ticker file
+pub fn gather_tickers(arguments: Vec<String>, file: Option<&str>) -> Vec<String> {
+    let mut tickers: Vec<String> = vec![];
+    for argument in arguments {
+        if argument == "-" {
+            let mut text = String::new();
+            std::io::Read::read_to_string(&mut std::io::stdin(), &mut text)
+                .unwrap_or_else(|error| fail(&format!("Failed to read stdin: {}", error)));
+            tickers.append(&mut transfer::tickers(&text));
+        } else {
+            tickers.push(argument);
+        }
+    }
+    if let Some(file) = file {
+        let text = std::fs::read_to_string(file)
+            .unwrap_or_else(|error| fail(&format!("Failed to read {}: {}", file, error)));
+        tickers.append(&mut transfer::tickers(&text));
     }
+    tickers
+}
+
+///Checks tickers with Yahoo Finance, writing which were accepted and rejected
+///
+///Returns None after reporting the error if Yahoo Finance could not be asked, so no ticker is dropped for it
+fn verify(tickers: &[String]) -> Option<Vec<String>> {
+    let (from, now) = format::get_time("1d");
+    let cfg = config::load();
+    let mut accepted: Vec<String> = vec![];
+    let mut rejected: Vec<String> = vec![];
+    for ticker in tickers.iter() {
+        match yahoo::exists(ticker, from, now) {
+            yahoo::Lookup::Found => accepted.push(ticker.to_string()),
+            yahoo::Lookup::Missing => rejected.push(ticker.to_string()),
+            yahoo::Lookup::Failed(error) => {
+                finish_with_error(
+                    &cfg,
+                    &format!(
+                        "Could not check {} with Yahoo Finance: {}. Nothing was changed.",
+                        ticker, error
+                    ),
+                );
+                return None;
+            }
+        }
+    }
+    if !accepted.is_empty() && interactive() {
+        output::write_then_nextline(format!("Accepted: {}", accepted.join(", ")).as_ref());
+    }
+    if !rejected.is_empty() {
+        warn(&cfg, format!("Rejected: {}", rejected.join(", ")).as_ref());
+    }
+    if !tickers.is_empty() && interactive() {
+        output::skip_line();
+    }
+    Some(accepted)
 }
 
 ///Removes ticker/s to watchlist
 pub fn remove(watchlist: &str, tickers: Vec<String>) {
-    output::setup();
+    begin();
     if tickers.is_empty() {
-        output::write_then_nextline("You did not provide any ticker.");
-        output::skip_line();
-        output::write_then_nextline("Use delete to delete a watchlist.");
-        output::write_then_nextline("Use remove to remove ticker/s from a watchlist.");
-        output::skip_line();
-        output::write("Operation aborted. Press q to quit...");
-        let mut event = read().expect("Terminal error.");
-        while event != Event::Key(KeyCode::Char('q').into()) {
-            event = read().expect("Terminal error.");
+        let cfg = config::load();
+        warn(&cfg, "You did not provide any ticker.");
+        if interactive() {
+            output::skip_line();
+            output::write_then_nextline("Use delete to delete a watchlist.");
+            output::write_then_nextline("Use remove to remove ticker/s from a watchlist.");
+            output::skip_line();
         }
+        finish_with_error(&cfg, "Operation aborted.");
     } else {
-        let mut cfg: Config = confy::load("zigfi").expect("Failed to load zigfi configuration.");
-        let mut clone = cfg
-            .watchlists
-            .get(watchlist)
-            .expect("Failed to load watchlist.")
-            .clone();
-        let mut for_removal: Vec<usize> = vec![];
-        for ticker in tickers.iter() {
-            let index = cfg
-                .watchlists
-                .get(watchlist)
-                .expect("Failed to load watchlist.")
-                .iter()
-                .position(|x| x == ticker)
-                .expect("Failed to find ticker/s. Invalid ticker/s?");
-            for_removal.push(index);
-        }
-        for index in for_removal {
-            clone.remove(index);
+        let lock = config::lock();
+        let mut cfg = config::load();
+        let current = match cfg.watchlists.get(watchlist) {
+            Some(current) => current.clone(),
+            None => {
+                drop(lock);
+                finish_with_error(&cfg, "Watchlist does not exist.");
+                return;
+            }
+        };
+        let (kept, not_found) = strip(&current, &tickers);
+        let removed = current.len() - kept.len();
+        cfg.watchlists.insert(watchlist.to_string(), kept);
+        config::store(&cfg);
+        drop(lock);
+        if !not_found.is_empty() {
+            warn(
+                &cfg,
+                format!("Not found: {}", not_found.join(", ")).as_ref(),
+            );
         }
-        cfg.watchlists.insert(watchlist.to_string(), clone);
-        confy::store("zigfi", cfg).expect("Failed to save.");
-        output::write_then_nextline("Ticker/s has been removed from the watchlist.");
-        output::write("Press q to quit...");
-        let mut event = read().expect("Terminal error.");
-        while event != Event::Key(KeyCode::Char('q').into()) {
-            event = read().expect("Terminal error.");
+        finish_partial(
+            format!("{} ticker/s removed from the watchlist.", removed).as_ref(),
+            !not_found.is_empty(),
+        );
+    }
+}
+
+///Returns tickers no pattern matches, and the patterns that matched none of them
+fn strip<'a>(current: &[String], patterns: &'a [String]) -> (Vec<String>, Vec<&'a str>) {
+    let not_found = patterns
+        .iter()
+        .filter(|pattern| !current.iter().any(|ticker| matches(pattern, ticker)))
+        .map(String::as_str)
+        .collect();
+    let kept = current
+        .iter()
+        .filter(|ticker| !patterns.iter().any(|pattern| matches(pattern, ticker)))
+        .cloned()
+        .collect();
+    (kept, not_found)
+}
+
+///Checks ticker against a pattern ignoring case, where * matches any text and ? any single character
+fn matches(pattern: &str, ticker: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_uppercase().chars().collect();
+    let ticker: Vec<char> = ticker.to_uppercase().chars().collect();
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+    while t < ticker.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == ticker[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if let Some((star, matched)) = backtrack {
+            p = star + 1;
+            t = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
         }
     }
+    pattern[p..].iter().all(|c| *c == '*')
 }
 
 ///Deletes an existing watchlist
 pub fn delete(query: &str) {
-    output::setup();
-    let mut cfg: Config = confy::load("zigfi").expect("Failed to load zigfi configuration.");
+    begin();
+    let cfg = config::load();
+    if !cfg.watchlists.contains_key(query) {
+        finish_with_error(&cfg, "Watchlist does not exist.");
+        return;
+    }
+    if !confirm(format!("Watchlist \"{}\" will be deleted.", query).as_ref()) {
+        declined();
+        return;
+    }
+    let lock = config::lock();
+    let mut cfg = config::load();
     if cfg.watchlists.contains_key(query) {
         cfg.watchlists.remove(query);
-        confy::store("zigfi", cfg).expect("Failed to save.");
-        output::write_then_nextline("Watchlist has been deleted.");
-        output::write("Press q to quit...");
-        let mut event = read().expect("Terminal error.");
-        while event != Event::Key(KeyCode::Char('q').into()) {
-            event = read().expect("Terminal error.");
-        }
+        config::store(&cfg);
+        drop(lock);
+        finish("Watchlist has been deleted. Use undo to bring it back.");
     } else {
-        output::write_then_nextline("Watchlist does not exist.");
-        output::write("Press q to quit...");
-        let mut event = read().expect("Terminal error.");
-        while event != Event::Key(KeyCode::Char('q').into()) {
-            event = read().expect("Terminal error.");
+        drop(lock);
+        finish_with_error(&cfg, "Watchlist does not exist.");
+    }
+}
+
+///Renames a watchlist
+pub fn rename(old: &str, new: &str) {
+    relocate(old, new, true);
+}
+
+///Copies a watchlist under a new name
+pub fn copy(source: &str, destination: &str) {
+    relocate(source, destination, false);
+}
+
+///Stores watchlist under another name, removing the original when renaming
+fn relocate(source: &str, destination: &str, rename: bool) {
+    begin();
+    let cfg = config::load();
+    if !cfg.watchlists.contains_key(source) {
+        finish_with_error(&cfg, "Watchlist does not exist.");
+        return;
+    }
+    if source != destination && !overwrite_allowed(&cfg, destination) {
+        declined();
+        return;
+    }
+    let lock = config::lock();
+    let mut cfg = config::load();
+    let tickers = match cfg.watchlists.get(source) {
+        Some(tickers) => tickers.clone(),
+        None => fail("Watchlist was removed while waiting."),
+    };
+    if rename {
+        cfg.watchlists.remove(source);
+    }
+    cfg.watchlists.insert(destination.to_string(), tickers);
+    config::store(&cfg);
+    drop(lock);
+    if rename {
+        finish("Watchlist has been renamed.");
+    } else {
+        finish("Watchlist has been copied.");
+    }
+}
+
+///Combines tickers of two watchlists into one, keeping the first occurrence of each ticker
+pub fn merge(first: &str, second: &str, destination: &str) {
+    begin();
+    let cfg = config::load();
+    if !cfg.watchlists.contains_key(first) || !cfg.watchlists.contains_key(second) {
+        finish_with_error(&cfg, "Watchlist does not exist.");
+        return;
+    }
+    if destination != first && destination != second && !overwrite_allowed(&cfg, destination) {
+        declined();
+        return;
+    }
+    let lock = config::lock();
+    let mut cfg = config::load();
+    let mut merged: Vec<String> = vec![];
+    for name in [first, second] {
+        for ticker in cfg.watchlists.get(name).into_iter().flatten() {
+            if !merged.contains(ticker) {
+                merged.push(ticker.clone());
+            }
+        }
+    }
+    cfg.watchlists.insert(destination.to_string(), merged);
+    config::store(&cfg);
+    drop(lock);
+    finish("Watchlists have been merged.");
+}
+
+///Moves ticker to a position in the watchlist, starting from 1
+pub fn move_ticker(watchlist: &str, ticker: &str, position: &str) {
+    begin();
+    let position: usize = match position.parse() {
+        Ok(position) if position > 0 => position,
+        _ => {
+            let cfg = config::load();
+            finish_with_error(&cfg, "Position should be a number starting from 1.");
+            return;
+        }
+    };
+    let lock = config::lock();
+    let mut cfg = config::load();
+    let tickers = match cfg.watchlists.get_mut(watchlist) {
+        Some(tickers) => tickers,
+        None => {
+            drop(lock);
+            finish_with_error(&cfg, "Watchlist does not exist.");
+            return;
         }
+    };
+    let index = match tickers.iter().position(|existing| existing == ticker) {
+        Some(index) => index,
+        None => {
+            drop(lock);
+            finish_with_error(&cfg, "Ticker is not on the watchlist.");
+            return;
+        }
+    };
+    let moved = tickers.remove(index);
+    tickers.insert((position - 1).min(tickers.len()), moved);
+    config::store(&cfg);
+    drop(lock);
+    finish("Ticker has been moved.");
+}
+
+///Sorts watchlist by ticker symbol or by the day's change, biggest gain first
+pub fn sort(watchlist: &str, by: &str) {
+    begin();
+    let cfg = config::load();
+    let tickers = match cfg.watchlists.get(watchlist) {
+        Some(tickers) => tickers.clone(),
+        None => {
+            finish_with_error(&cfg, "Watchlist does not exist.");
+            return;
+        }
+    };
+    //Prices are requested before locking, as they are slow
+    let changes: HashMap<String, f64> = match by {
+        "symbol" => HashMap::new(),
+        "change" => {
+            if interactive() {
+                output::write_then_nextline("Requesting prices...");
+            }
+            let (from, to) = format::get_time("1d");
+            tickers
+                .into_iter()
+                .map(|ticker| {
+                    let difference = yahoo::get(&ticker, from, to).difference;
+                    (ticker, difference)
+                })
+                .collect()
+        }
+        _ => {
+            finish_with_error(&cfg, "Unknown sort order. Use --by symbol or --by change.");
+            return;
+        }
+    };
+    //Sorts what the watchlist holds now, it may have changed while prices were requested
+    let lock = config::lock();
+    let mut cfg = config::load();
+    let current = match cfg.watchlists.get_mut(watchlist) {
+        Some(current) => current,
+        None => {
+            drop(lock);
+            finish_with_error(&cfg, "Watchlist does not exist.");
+            return;
+        }
+    };
+    if by == "symbol" {
+        current.sort_by_key(|ticker| ticker.to_uppercase());
+    } else {
+        //Tickers added meanwhile have no change and keep their order at the end
+        current.sort_by(|a, b| match (changes.get(a), changes.get(b)) {
+            (Some(a), Some(b)) => b.total_cmp(a),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => std::cmp::Ordering::Equal,
+        });
+    }
+    config::store(&cfg);
+    drop(lock);
+    finish("Watchlist has been sorted.");
+}
+
+///Asks before overwriting an existing watchlist, like new does
+fn overwrite_allowed(cfg: &Config, watchlist: &str) -> bool {
+    !cfg.watchlists.contains_key(watchlist)
+        || confirm(
+            format!(
+                "Watchlist \"{}\" already exist and will be overwritten.",
+                watchlist
+            )
+            .as_ref(),
+        )
+}
+
+///Writes message and waits for q
+fn finish(message: &str) {
+    if !interactive() {
+        say(message);
+        return;
+    }
+    output::write_then_nextline(message);
+    output::write("Press q to quit...");
+    let mut event = read().expect("Terminal error.");
+    while event != Event::Key(KeyCode::Char('q').into()) {
+        event = read().expect("Terminal error.");
+    }
+}
+
+///Writes message in the theme's error color and waits for q
+fn finish_with_error(cfg: &Config, message: &str) {
+    if !interactive() {
+        fail(message);
+    }
+    warn(cfg, message);
+    output::write("Press q to quit...");
+    let mut event = read().expect("Terminal error.");
+    while event != Event::Key(KeyCode::Char('q').into()) {
+        event = read().expect("Terminal error.");
     }
 }
 
 ///Displays commands available
 pub fn help() {
-    output::setup();
     let help = vec![
         "zigfi - List of Commands",
         "",
         "Commands",
         "zigfi (shows \"default\" watchlist)",
         "zigfi new <watchlist name> <optional: ticker/s>",
+        "zigfi new <watchlist name> - (reads tickers from stdin, one per line or comma separated)",
         "zigfi show <watchlist name> <optional: interval> (interval can be \"1d\", \"1mo\" or \"1y\")",
+        "zigfi show <watchlist name> --interval 1d|1mo|1y --format table|plain|heatmap|text|json",
+        "zigfi show <watchlist name> --sort symbol|change --watch <seconds>",
+        "zigfi show <watchlist name> --columns rsi14,sma50 (adds sma, ema, rsi, macd, macdsignal, macdhist, bbupper, bblower or atr columns)",
+        "Add \"--plain\" to show without Alternate Screen, for screen readers",
+        "Add \"--heatmap\" to show the watchlist as tiles colored by the size of each move",
         "zigfi delete <watchlist name>",
+        "zigfi rename <watchlist name> <new name>",
+        "zigfi copy <watchlist name> <new name>",
+        "zigfi merge <watchlist name> <watchlist name> into <new name>",
+        "zigfi move <watchlist name> <ticker> <position starting from 1>",
+        "zigfi sort <watchlist name> --by symbol|change",
         "zigfi add <watchlist name> <ticker/s>",
-        "zigfi remove <watchlist name> <ticker/s>",
-        "zigfi search <name of asset>",
+        "zigfi add <watchlist name> --from-file <file> (# starts a comment line)",
+        "zigfi remove <watchlist name> <ticker/s or patterns like '*-USD'>",
+        "zigfi quote <ticker/s> <optional: --interval 1d|1mo|1y> (shows tickers without a watchlist)",
+        "zigfi search <name of asset> (type to refine, Space picks results, Enter adds them to a watchlist)",
+        "zigfi search <name of asset> --type equity|etf|crypto|future|currency|index|mutualfund --exchange <code> --format text|json|csv",
+        "zigfi performance <optional: watchlist name> --format table|text|json|csv (1D, 1W, 1M, 3M, YTD, 1Y and 5Y returns)",
+        "zigfi compare <ticker/s> --range 1mo|1y|5y|max --format chart|text|json|csv (starts every ticker from 100)",
+        "zigfi compare <ticker/s> --columns sma50,bbupper20 (draws indicators over every ticker on the chart)",
+        "zigfi risk <optional: watchlist name> --benchmark <ticker> --range 1y|5y (volatility, drawdown, Sharpe and beta)",
+        "zigfi correlation <optional: watchlist name> --range 1y|5y (correlation matrix of daily returns)",
+        "zigfi history <ticker> --range 1mo|1y|5y|max --bar 1h|1d|1wk|1mo --format table|csv|json",
         "zigfi list (lists saved watchlist/s)",
         "zigfi colorswap (swaps Green and Red for some East Asian users)",
+        "zigfi theme <optional: theme name> (lists themes or switches to one)",
+        "zigfi config path|get <key>|set <key> <value>|edit|validate",
+        "zigfi export <optional: watchlist name/s> --format json|toml|csv|txt",
+        "zigfi import <file> <optional: --merge or --replace>",
+        "zigfi undo (reverts the last change to watchlists or settings)",
+        "zigfi backups list|restore <number>",
+        "zigfi completions bash|zsh|fish (prints shell completion script)",
+        "zigfi <command> --help (usage and options of a command)",
+        "zigfi --version",
         "zigfi help",
         "",
-        "\"zigfi show\" supports piping. Default output is string. Add \"--json\" for JSON.",
+        "\"zigfi show\", \"zigfi quote\", \"zigfi performance\" and \"zigfi search\" support piping. Default output is string.",
+        "\"zigfi history\" prints CSV when piped, for notebooks and backtests.",
+        "Add \"--json\" for JSON or \"--format csv\" for CSV.",
+        "",
+        "Commands that change watchlists or settings print a line instead of waiting for q",
+        "when piped or run with --yes, --no or --quiet. --yes and --no answer their prompts.",
+        "Exit status is 0 when done, 1 on errors, 2 when not confirmed, 3 when some",
+        "tickers were rejected or not found and 64 when the command line is invalid.",
         "",
         "Developed by Aldrin Zigmund Cortez Velasco",
         "",
         "Press q to quit...",
     ];
+    if !interactive() {
+        for line in &help[..help.len() - 1] {
+            println!("{}", line);
+        }
+        return;
+    }
+    output::setup();
     output::write_multiline(help);
     let mut event = read().expect("Terminal error.");
     while event != Event::Key(KeyCode::Char('q').into()) {
@@ -346,33 +1810,513 @@ pub fn help() {
 
 ///Lists watchlists
 pub fn list() {
+    let cfg = config::load();
+    if !interactive() {
+        for watchlist in cfg.watchlists.keys() {
+            say(watchlist);
+        }
+        return;
+    }
     output::setup();
-    let cfg: Config = confy::load("zigfi").expect("Failed to load zigfi configuration.");
     for watchlist in cfg.watchlists.keys() {
         output::write_then_nextline(watchlist);
     }
     output::skip_line();
-    output::write_then_nextline("Existing watchlist/s displayed.");
-    output::write("Press q to quit...");
-    let mut event = read().expect("Terminal error.");
-    while event != Event::Key(KeyCode::Char('q').into()) {
-        event = read().expect("Terminal error.");
-    }
+    finish("Existing watchlist/s displayed.");
 }
 
 ///Swaps red and green for some East Asian users
 pub fn colorswap() {
-    output::setup();
-    let mut cfg: Config = confy::load("zigfi").expect("Failed to load zigfi configuration.");
-    if cfg.greenisup {
-        cfg.greenisup = false;
+    begin();
+    let lock = config::lock();
+    let mut cfg = config::load();
+    cfg.greenisup = !cfg.greenisup;
+    config::store(&cfg);
+    drop(lock);
+    finish("Green and red swapped.");
+}
+
+///Lists available themes or switches to the provided one
+pub fn theme(name: Option<&str>) {
+    begin();
+    let lock = config::lock();
+    let mut cfg = config::load();
+    match name {
+        Some(name) if cfg.themes.contains_key(name) || theme::Theme::builtin(name).is_some() => {
+            cfg.theme = name.to_string();
+            config::store(&cfg);
+            drop(lock);
+            finish("Theme changed.");
+        }
+        Some(_) => {
+            drop(lock);
+            finish_with_error(&cfg, "Theme does not exist.");
+        }
+        None => {
+            drop(lock);
+            let mut names: Vec<&str> = theme::BUILTIN.to_vec();
+            for name in cfg.themes.keys() {
+                if !names.contains(&name.as_str()) {
+                    names.push(name);
+                }
+            }
+            for name in names {
+                if name == cfg.theme() {
+                    say(format!("{} (active)", name).as_ref());
+                } else {
+                    say(name);
+                }
+            }
+            if interactive() {
+                output::skip_line();
+                finish("Available theme/s displayed.");
+            }
+        }
+    }
+}
+
+///How commands that change watchlists or settings talk to the user
+#[derive(Clone, Copy)]
+struct Mode {
+    ///Uses the Alternate Screen and waits for keys, otherwise prints a line and exits with a status
+    interactive: bool,
+    ///Answer given to prompts by --yes or --no
+    answer: Option<bool>,
+    ///Prints nothing but errors
+    quiet: bool,
+}
+
+static MODE: OnceLock<Mode> = OnceLock::new();
+
+///Exit status when a confirmation was declined or could not be asked
+const DECLINED: i32 = 2;
+
+///Exit status when only some of the provided tickers were used
+const PARTIAL: i32 = 3;
+
+///Answers prompts with --yes or --no and silences output with --quiet, all of them skipping the Alternate Screen
+pub fn use_mode(yes: bool, no: bool, quiet: bool) {
+    let _ = MODE.set(Mode {
+        interactive: atty::is(atty::Stream::Stdout) && !yes && !no && !quiet,
+        answer: if yes {
+            Some(true)
+        } else if no {
+            Some(false)
+        } else {
+            None
+        },
+        quiet,
+    });
+}
+
+///Returns mode set with use_mode, interactive only on a terminal if none was set
+fn mode() -> Mode {
+    *MODE.get_or_init(|| Mode {
+        interactive: atty::is(atty::Stream::Stdout),
+        answer: None,
+        quiet: false,
+    })
+}
+
+///Checks whether to use the Alternate Screen and wait for keys
+fn interactive() -> bool {
+    mode().interactive
+}
+
+///Sets up the Alternate Screen unless running from a script
+fn begin() {
+    if interactive() {
+        output::setup();
+    }
+}
+
+///Writes a line of output, printed plainly when running from a script
+fn say(line: &str) {
+    if interactive() {
+        output::write_then_nextline(line);
+    } else if !mode().quiet {
+        println!("{}", line);
+    }
+}
+
+///Writes a line in the theme's error color, printed to stderr when running from a script
+fn warn(cfg: &Config, line: &str) {
+    if interactive() {
+        output::set_color(palette(cfg).error);
+        output::write_then_nextline(line);
+        output::reset_color();
     } else {
-        cfg.greenisup = true;
+        eprintln!("{}", line);
     }
-    confy::store("zigfi", cfg).expect("Failed to save new configuration.");
-    output::write("Green and red swapped. Press q to quit...");
-    let mut event = read().expect("Terminal error.");
-    while event != Event::Key(KeyCode::Char('q').into()) {
-        event = read().expect("Terminal error.");
+}
+
+///Asks whether to continue, answered by --yes or --no when running from a script
+fn confirm(message: &str) -> bool {
+    if !interactive() {
+        return mode().answer.unwrap_or_else(|| {
+            eprintln!("{} Use --yes or --no to answer.", message);
+            std::process::exit(DECLINED);
+        });
+    }
+    output::write_then_nextline(message);
+    output::write_then_nextline("Do you wish to continue? (y/n)");
+    loop {
+        let event = read().expect("Terminal error.");
+        if event == Event::Key(KeyCode::Char('y').into()) {
+            output::skip_line();
+            return true;
+        } else if event == Event::Key(KeyCode::Char('n').into()) {
+            return false;
+        }
+    }
+}
+
+///Ends a command whose confirmation was declined
+fn declined() {
+    if !interactive() {
+        say("Nothing changed.");
+        std::process::exit(DECLINED);
+    }
+}
+
+///Like finish, exiting with a distinct status from scripts when some tickers were left out
+fn finish_partial(message: &str, partial: bool) {
+    finish(message);
+    if partial && !interactive() {
+        std::process::exit(PARTIAL);
+    }
+}
+
+///Prints error and exits with a failure status, for commands meant to be scripted
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(1);
+}
+
+///Shows, changes, edits or validates the configuration
+pub fn configure(action: &str, key: Option<&str>, value: Option<&str>) {
+    match action {
+        "path" => println!("{}", config::path().display()),
+        "get" => {
+            let key = key.unwrap_or_else(|| fail("Usage: zigfi config get <key>"));
+            match config::get(&config::load(), key) {
+                Ok(value) => println!("{}", value),
+                Err(error) => fail(&error),
+            }
+        }
+        "set" => {
+            let (key, value) = match (key, value) {
+                (Some(key), Some(value)) => (key, value),
+                _ => fail("Usage: zigfi config set <key> <value>"),
+            };
+            let lock = config::lock();
+            match config::set(&config::load(), key, value) {
+                Ok(cfg) => {
+                    config::store(&cfg);
+                    drop(lock);
+                    println!("{} set.", key);
+                }
+                Err(error) => fail(&error),
+            }
+        }
+        "edit" => configure_edit(),
+        "validate" => {
+            let path = config::path();
+            let text = std::fs::read_to_string(&path).unwrap_or_else(|error| {
+                fail(&format!("Failed to read {}: {}", path.display(), error))
+            });
+            match config::parse(&text).and_then(|(cfg, _)| config::validate(&cfg)) {
+                Ok(()) => println!("Configuration is valid."),
+                Err(error) => fail(&error),
+            }
+        }
+        _ => fail("Unknown config action. Use path, get, set, edit or validate."),
+    }
+}
+
+///Opens a copy of the configuration in $VISUAL or $EDITOR and saves it only once it is valid
+fn configure_edit() {
+    let path = config::path();
+    let draft = path.with_extension("toml.edit");
+    //A profile or file that does not exist yet starts from the defaults
+    let copied = if path.exists() {
+        std::fs::copy(&path, &draft).map(|_| ())
+    } else {
+        path.parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|_| {
+                let defaults = toml::to_string_pretty(&Config::default())
+                    .expect("Failed to write zigfi configuration.");
+                std::fs::write(&draft, defaults)
+            })
+    };
+    if let Err(error) = copied {
+        fail(&format!(
+            "Failed to copy {} for editing: {}",
+            path.display(),
+            error
+        ));
+    }
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    let mut words = editor.split_whitespace();
+    let program = words.next().unwrap_or("vi");
+    let options: Vec<&str> = words.collect();
+    loop {
+        let status = std::process::Command::new(program)
+            .args(&options)
+            .arg(&draft)
+            .status()
+            .unwrap_or_else(|error| fail(&format!("Failed to open {}: {}", program, error)));
+        if !status.success() {
+            let _ = std::fs::remove_file(&draft);
+            fail("Editor exited with an error. Changes discarded.");
+        }
+        let text = std::fs::read_to_string(&draft).expect("Failed to read edited configuration.");
+        match config::parse(&text).and_then(|(cfg, _)| config::validate(&cfg)) {
+            Ok(()) => {
+                let lock = config::lock();
+                config::store_text(&text);
+                drop(lock);
+                let _ = std::fs::remove_file(&draft);
+                println!("Configuration saved.");
+                break;
+            }
+            Err(error) => {
+                eprintln!("{}", error);
+                println!("Edit again? (y/n)");
+                let mut answer = String::new();
+                std::io::stdin()
+                    .read_line(&mut answer)
+                    .expect("Terminal error.");
+                if !answer.trim().eq_ignore_ascii_case("y") {
+                    let _ = std::fs::remove_file(&draft);
+                    fail("Changes discarded.");
+                }
+            }
+        }
+    }
+}
+
+///Restores the configuration from before the last change
+pub fn undo() {
+    begin();
+    let lock = config::lock();
+    let cfg = config::load();
+    let result = match config::backups().first() {
+        Some(latest) => config::restore(latest, true).map(|_| true),
+        None => Ok(false),
+    };
+    drop(lock);
+    match result {
+        Ok(true) => finish("Last change has been undone."),
+        Ok(false) => finish("Nothing to undo."),
+        Err(error) => finish_with_error(&cfg, &error),
+    }
+}
+
+///Lists configuration backups or restores one of them by its number on the list
+pub fn backups(action: Option<&str>, number: Option<&str>) {
+    begin();
+    let cfg = config::load();
+    let backups = config::backups();
+    match action {
+        Some("restore") => {
+            let lock = config::lock();
+            let chosen = number
+                .and_then(|number| number.parse::<usize>().ok())
+                .and_then(|number| number.checked_sub(1))
+                .and_then(|index| backups.get(index));
+            let result = match chosen {
+                Some(backup) => config::restore(backup, false).map(|_| ()),
+                None => Err("Backup not found. Use backups list to see their numbers.".to_string()),
+            };
+            drop(lock);
+            match result {
+                Ok(()) => finish("Backup has been restored."),
+                Err(error) => finish_with_error(&cfg, &error),
+            }
+        }
+        None | Some("list") => {
+            let locale = locale(&cfg);
+            for (index, backup) in backups.iter().enumerate() {
+                let time = config::backup_time(backup)
+                    .map(|time| format::timestamp(time.with_timezone(&chrono::Local), &locale))
+                    .unwrap_or_default();
+                let watchlists = std::fs::read_to_string(backup)
+                    .ok()
+                    .and_then(|text| config::parse(&text).ok())
+                    .map(|(backup, _)| backup.watchlists.len())
+                    .unwrap_or(0);
+                if interactive() {
+                    output::write_within_space(format!("{}", index + 1).as_ref(), 5);
+                    output::write_within_space(time.as_ref(), 30);
+                    output::write_then_nextline(format!("{} watchlist/s", watchlists).as_ref());
+                } else {
+                    say(format!("{}\t{}\t{} watchlist/s", index + 1, time, watchlists).as_ref());
+                }
+            }
+            if interactive() {
+                if backups.is_empty() {
+                    output::write_then_nextline("No backups yet.");
+                }
+                output::skip_line();
+                finish("Use backups restore <number> to go back to one.");
+            }
+        }
+        Some(_) => finish_with_error(&cfg, "Use backups list or backups restore <number>."),
+    }
+}
+
+///Prints watchlists in a file format for moving them between machines, all of them if none are named
+pub fn export(names: Vec<String>, format: &str) {
+    let format = transfer::Format::from_name(format)
+        .unwrap_or_else(|| fail("Unknown format. Use json, toml, csv or txt."));
+    let cfg = config::load();
+    let mut names = names;
+    if names.is_empty() {
+        names = cfg.watchlists.keys().cloned().collect();
+        names.sort();
+    }
+    let mut watchlists: transfer::Watchlists = vec![];
+    for name in names {
+        match cfg.watchlists.get(&name) {
+            Some(tickers) => watchlists.push((name, tickers.clone())),
+            None => fail(&format!("Watchlist \"{}\" does not exist.", name)),
+        }
+    }
+    print!("{}", transfer::export(&watchlists, format));
+}
+
+///Ways import handles watchlists that already exist
+#[derive(Clone, Copy, PartialEq)]
+pub enum Conflict {
+    Ask,
+    Merge,
+    Replace,
+}
+
+///Imports watchlists from a file written by export
+pub fn import(file: &str, format: Option<&str>, conflict: Conflict) {
+    let path = std::path::Path::new(file);
+    let format = match format {
+        Some(name) => transfer::Format::from_name(name),
+        None => transfer::Format::from_path(path),
+    }
+    .unwrap_or_else(|| fail("Unknown format. Use --format json, toml, csv or txt."));
+    let text = std::fs::read_to_string(path)
+        .unwrap_or_else(|error| fail(&format!("Failed to read {}: {}", file, error)));
+    let watchlists = transfer::import(&text, format).unwrap_or_else(|error| fail(&error));
+    begin();
+    let existing = config::load();
+    let mut accepted: transfer::Watchlists = vec![];
+    let mut partial = false;
+    for (name, tickers) in watchlists {
+        if conflict != Conflict::Ask || overwrite_allowed(&existing, &name) {
+            let verified_tickers = match verify(&tickers) {
+                Some(verified_tickers) => verified_tickers,
+                None => return,
+            };
+            partial |= verified_tickers.len() < tickers.len();
+            accepted.push((name, verified_tickers));
+        }
+    }
+    let lock = config::lock();
+    let mut cfg = config::load();
+    let mut kept: Vec<&str> = vec![];
+    for (name, tickers) in accepted.iter() {
+        //An empty list never replaces one with tickers, it is more likely lost than meant
+        if tickers.is_empty()
+            && cfg
+                .watchlists
+                .get(name)
+                .is_some_and(|current| !current.is_empty())
+        {
+            kept.push(name);
+            continue;
+        }
+        let merged = match cfg.watchlists.get(name) {
+            Some(current) if conflict == Conflict::Merge => {
+                let mut merged = current.clone();
+                for ticker in tickers {
+                    if !merged.contains(ticker) {
+                        merged.push(ticker.clone());
+                    }
+                }
+                merged
+            }
+            _ => tickers.clone(),
+        };
+        cfg.watchlists.insert(name.clone(), merged);
+    }
+    config::store(&cfg);
+    drop(lock);
+    if !kept.is_empty() {
+        warn(
+            &cfg,
+            format!("Kept as they were instead of emptied: {}", kept.join(", ")).as_ref(),
+        );
+    }
+    finish_partial(
+        format!("{} watchlist/s imported.", accepted.len() - kept.len()).as_ref(),
+        partial || !kept.is_empty(),
+    );
+}
+
+///Prints completion script for a shell
+pub fn completions(shell: &str) {
+    match completions::script(shell) {
+        Some(script) => print!("{}", script),
+        None => fail("Unknown shell. Use bash, zsh or fish."),
+    }
+}
+
+///Prints completion candidates for the words typed so far, one per line
+pub fn complete(words: &[String]) {
+    for candidate in completions::candidates(words) {
+        println!("{}", candidate);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list(tickers: &[&str]) -> Vec<String> {
+        tickers.iter().map(|ticker| ticker.to_string()).collect()
+    }
+
+    #[test]
+    fn matches_ignores_case() {
+        assert!(matches("aapl", "AAPL"));
+        assert!(matches("BTC-usd", "btc-USD"));
+    }
+
+    #[test]
+    fn matches_whole_ticker_without_wildcards() {
+        assert!(!matches("AAP", "AAPL"));
+        assert!(!matches("AAPL", "AAP"));
+        assert!(!matches("", "AAPL"));
+    }
+
+    #[test]
+    fn matches_wildcards() {
+        assert!(matches("*-USD", "BTC-USD"));
+        assert!(matches("BTC*", "BTC-USD"));
+        assert!(matches("*", "GC=F"));
+        assert!(matches("?C=F", "GC=F"));
+        assert!(!matches("?C=F", "GGC=F"));
+        assert!(matches("B*D", "BTC-USD"));
+        assert!(!matches("*-EUR", "BTC-USD"));
+    }
+
+    #[test]
+    fn strip_removes_several_tickers_at_once() {
+        let current = list(&["AAPL", "BTC-USD", "ETH-USD", "GC=F"]);
+        let patterns = list(&["aapl", "*-USD", "MSFT"]);
+        let (kept, not_found) = strip(&current, &patterns);
+        assert_eq!(kept, list(&["GC=F"]));
+        assert_eq!(not_found, vec!["MSFT"]);
     }
 }
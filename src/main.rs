@@ -1,125 +1,197 @@
 #![forbid(unsafe_code)]
 
+use std::time::Duration;
+
 use atty::Stream;
-use zigarg::Arguments;
 use zigfi::{
-    self, add, colorswap, delete, display, help, list, new, print, print_json, remove, search,
-    startup,
+    add, backups, check_tickers, cli, colorswap, compare, complete, completions, configure, copy,
+    correlation, delete, display, display_performance, export, gather_tickers, help, history,
+    import, indicators::Column, list, merge, move_ticker, new, output, print, print_csv,
+    print_json, remove, rename, risk, search, sort, startup, theme, undo, use_config, use_mode,
+    watchlist, Conflict, Order, View,
 };
 
-mod output;
-
 fn main() {
-    ///Makes panic! reset output back from Alternate Screen first before crashing for cleaner error message
+    //Makes panic! reset output back from Alternate Screen first before crashing for cleaner error message
     let default_panic = std::panic::take_hook();
     std::panic::set_hook(Box::new(move |info| {
         output::cleanup();
         default_panic(info);
     }));
 
-    ///Captures arguments using zigarg crate
-    let arguments = Arguments::new();
+    //Parses arguments, usage errors are printed before anything touches the terminal
+    let arguments: Vec<String> = std::env::args().skip(1).collect();
+    let parsed = match cli::parse(&arguments) {
+        Ok(parsed) => parsed,
+        Err(error) => {
+            eprintln!("{}", error);
+            eprintln!("Run zigfi --help for usage.");
+            std::process::exit(cli::USAGE);
+        }
+    };
+    if parsed.has("--version") {
+        println!("zigfi {}", env!("CARGO_PKG_VERSION"));
+        return;
+    }
+    if parsed.has("--help") {
+        if arguments
+            .iter()
+            .any(|argument| cli::command(argument).is_some())
+        {
+            print!("{}", cli::usage(parsed.command));
+        } else {
+            print!("{}", cli::overview());
+        }
+        return;
+    }
+
+    //Takes out options that apply to every command
+    use_config(
+        parsed.value("--config").map(String::from),
+        parsed.value("--profile").map(String::from),
+    );
+    use_mode(
+        parsed.has("--yes"),
+        parsed.has("--no"),
+        parsed.has("--quiet"),
+    );
 
-    ///Sets up default configuration if not available
+    //Completions and config checks run before startup so they never create, migrate or lock the configuration
+    match parsed.command.name {
+        "completions" => return completions(parsed.argument("shell").unwrap_or_default()),
+        "__complete" => return complete(parsed.arguments("words")),
+        //Inspecting or repairing the file must work even when it cannot be loaded
+        "config"
+            if matches!(
+                parsed.argument("action"),
+                Some("path" | "validate" | "edit")
+            ) =>
+        {
+            return configure(parsed.argument("action").unwrap_or_default(), None, None)
+        }
+        _ => {}
+    }
+
+    //Sets up default configuration if not available
     startup();
 
-    ///Variable to suppress cleanup on output if requester is not tty preventing ANSI escapes from being piped
+    //Variable to suppress cleanup on output if requester is not tty preventing ANSI escapes from being piped
     let mut clean_up_required = true;
 
-    //Processes arguments and executes request
-    if !arguments.has_args() && atty::is(Stream::Stdout) {
-        display("default", "1d");
-    } else if !arguments.has_args() && !arguments.exist("--json") {
-        print("default", "1d");
-    } else if !arguments.has_args() {
-        print_json("default", "1d");
-    } else if arguments.exist("show") && atty::is(Stream::Stdout) {
-        let mut interval = "1d";
-        if arguments.exist("1mo") {
-            interval = "1mo";
-        }
-        if arguments.exist("1y") {
-            interval = "1y";
-        }
-        display(
-            arguments
-                .get_value("show")
-                .expect("Something wrong with arguments. Please, double check."),
-            interval,
-        );
-    } else if arguments.exist("show") && arguments.exist("--json") {
-        clean_up_required = false;
-        let mut interval = "1d";
-        if arguments.exist("1mo") {
-            interval = "1mo";
-        }
-        if arguments.exist("1y") {
-            interval = "1y";
-        }
-        print_json(
-            arguments
-                .get_value("show")
-                .expect("Something wrong with arguments. Please, double check."),
-            interval,
-        );
-    } else if arguments.exist("show") {
-        clean_up_required = false;
-        let mut interval = "1d";
-        if arguments.exist("1mo") {
-            interval = "1mo";
-        }
-        if arguments.exist("1y") {
-            interval = "1y";
-        }
-        print(
-            arguments
-                .get_value("show")
-                .expect("Something wrong with arguments. Please, double check."),
-            interval,
-        );
-    } else if arguments.exist("search") {
-        search(
-            arguments
-                .get_value("search")
-                .expect("Something wrong with arguments. Please, double check."),
-        );
-    } else if arguments.exist("new") {
-        new(
-            arguments
-                .get_value("new")
-                .expect("Something wrong with arguments. Please, double check."),
-            arguments.get_after_index(3),
-        );
-    } else if arguments.exist("add") {
-        add(
-            arguments
-                .get_value("add")
-                .expect("Something wrong with arguments. Please, double check."),
-            arguments.get_after_index(3),
-        );
-    } else if arguments.exist("remove") {
-        remove(
-            arguments
-                .get_value("remove")
-                .expect("Something wrong with arguments. Please, double check."),
-            arguments.get_after_index(3),
-        );
-    } else if arguments.exist("delete") {
-        delete(
-            arguments
-                .get_value("delete")
-                .expect("Something wrong with arguments. Please, double check."),
-        );
-    } else if arguments.exist("help") || arguments.exist("--help") || arguments.exist("-h") {
-        help();
-    } else if arguments.exist("list") {
-        list();
-    } else if arguments.exist("colorswap") {
-        colorswap();
-    } else {
-        output::write_then_nextline("Command not found.");
-        output::skip_line();
-        help();
+    //Executes request
+    let argument = |name: &str| parsed.argument(name).unwrap_or_default();
+    match parsed.command.name {
+        "show" => {
+            let tickers = watchlist(parsed.argument("watchlist").unwrap_or("default"));
+            clean_up_required = show(&tickers, &parsed);
+        }
+        "quote" => {
+            check_tickers(parsed.arguments("tickers"));
+            clean_up_required = show(parsed.arguments("tickers"), &parsed);
+        }
+        "search" => {
+            let format = format(&parsed, "table", "text");
+            clean_up_required = format == "table";
+            search(
+                &parsed.arguments("query").join(" "),
+                format,
+                parsed.value("--type"),
+                parsed.value("--exchange"),
+            );
+        }
+        "performance" => {
+            let tickers = watchlist(parsed.argument("watchlist").unwrap_or("default"));
+            let format = format(&parsed, "table", "text");
+            clean_up_required = format == "table";
+            display_performance(&tickers, format);
+        }
+        "compare" => {
+            let format = format(&parsed, "chart", "text");
+            clean_up_required = format == "chart";
+            compare(
+                parsed.arguments("tickers"),
+                parsed.value("--range").unwrap_or("1y"),
+                format,
+                &columns(&parsed),
+            );
+        }
+        "risk" | "correlation" => {
+            clean_up_required = false;
+            let tickers = watchlist(parsed.argument("watchlist").unwrap_or("default"));
+            let range = parsed.value("--range").unwrap_or("1y");
+            let format = format(&parsed, "table", "csv");
+            if parsed.command.name == "risk" {
+                risk(&tickers, parsed.value("--benchmark"), range, format);
+            } else {
+                correlation(&tickers, range, format);
+            }
+        }
+        "history" => {
+            clean_up_required = false;
+            let format = format(&parsed, "table", "csv");
+            history(
+                argument("ticker"),
+                parsed.value("--range").unwrap_or("1y"),
+                parsed.value("--bar").unwrap_or("1d"),
+                format,
+            );
+        }
+        "new" => new(
+            argument("watchlist"),
+            gather_tickers(
+                parsed.arguments("tickers").to_vec(),
+                parsed.value("--from-file"),
+            ),
+        ),
+        "add" => add(
+            argument("watchlist"),
+            gather_tickers(
+                parsed.arguments("tickers").to_vec(),
+                parsed.value("--from-file"),
+            ),
+        ),
+        "remove" => remove(argument("watchlist"), parsed.arguments("tickers").to_vec()),
+        "delete" => delete(argument("watchlist")),
+        "rename" => rename(argument("watchlist"), argument("name")),
+        "copy" => copy(argument("watchlist"), argument("name")),
+        "merge" => merge(argument("watchlist"), argument("other"), argument("name")),
+        "move" => move_ticker(
+            argument("watchlist"),
+            argument("ticker"),
+            argument("position"),
+        ),
+        "sort" => sort(
+            argument("watchlist"),
+            parsed.value("--by").unwrap_or("symbol"),
+        ),
+        "list" => list(),
+        "colorswap" => colorswap(),
+        "theme" => theme(parsed.argument("name")),
+        "config" => configure(
+            argument("action"),
+            parsed.argument("key"),
+            parsed.argument("value"),
+        ),
+        "export" => {
+            clean_up_required = false;
+            export(
+                parsed.arguments("watchlists").to_vec(),
+                parsed.value("--format").unwrap_or("json"),
+            );
+        }
+        "import" => {
+            let mut conflict = Conflict::Ask;
+            if parsed.has("--merge") {
+                conflict = Conflict::Merge;
+            }
+            if parsed.has("--replace") {
+                conflict = Conflict::Replace;
+            }
+            import(argument("file"), parsed.value("--format"), conflict);
+        }
+        "undo" => undo(),
+        "backups" => backups(parsed.argument("action"), parsed.argument("number")),
+        _ => help(),
     }
 
     //Resets output back from Alternate Screen before Exit
@@ -127,3 +199,79 @@ fn main() {
         output::cleanup();
     }
 }
+
+///Shows tickers the way show options ask, returning whether the terminal needs cleanup
+fn show(tickers: &[String], parsed: &cli::Parsed) -> bool {
+    let interval = parsed
+        .value("--interval")
+        .or(parsed.argument("interval"))
+        .unwrap_or("1d");
+    if !cli::INTERVALS.contains(&interval) {
+        eprintln!("Invalid interval \"{}\". Use 1d, 1mo or 1y.", interval);
+        std::process::exit(cli::USAGE);
+    }
+    let mut format = parsed.value("--format");
+    for (flag, name) in [
+        ("--plain", "plain"),
+        ("--heatmap", "heatmap"),
+        ("--json", "json"),
+    ] {
+        if parsed.has(flag) {
+            format = Some(name);
+        }
+    }
+    let order = match parsed.value("--sort") {
+        Some("symbol") => Order::Symbol,
+        Some("change") => Order::Change,
+        _ => Order::Stored,
+    };
+    let columns = columns(parsed);
+    let watch = parsed
+        .value("--watch")
+        .and_then(|seconds| seconds.parse().ok())
+        .map(Duration::from_secs);
+    match format {
+        Some("json") => print_json(tickers, interval, order, watch, &columns),
+        Some("csv") => print_csv(tickers, interval, order, watch, &columns),
+        Some("text") => print(tickers, interval, order, watch, &columns),
+        Some("plain") => display(tickers, interval, View::Plain, order, watch, &columns),
+        Some("heatmap") => display(tickers, interval, View::Heatmap, order, watch, &columns),
+        Some(_) => display(tickers, interval, View::Table, order, watch, &columns),
+        None if atty::is(Stream::Stdout) => {
+            display(tickers, interval, View::Table, order, watch, &columns)
+        }
+        None => print(tickers, interval, order, watch, &columns),
+    }
+    !matches!(format, Some("json") | Some("csv") | Some("text"))
+}
+
+///Returns format asked with --format or --json, else the default for a terminal or for a pipe
+fn format<'a>(parsed: &'a cli::Parsed, terminal: &'a str, pipe: &'a str) -> &'a str {
+    match parsed.value("--format") {
+        _ if parsed.has("--json") => "json",
+        Some(format) => format,
+        None if atty::is(Stream::Stdout) => terminal,
+        None => pipe,
+    }
+}
+
+///Reads indicator columns of --columns, exiting on unknown names
+fn columns(parsed: &cli::Parsed) -> Vec<Column> {
+    let mut columns = vec![];
+    for name in parsed.value("--columns").unwrap_or_default().split(',') {
+        if name.trim().is_empty() {
+            continue;
+        }
+        match Column::from_name(name) {
+            Some(column) => columns.push(column),
+            None => {
+                eprintln!(
+                    "Invalid column \"{}\". Use sma, ema, rsi, macd, macdsignal, macdhist, bbupper, bblower or atr, with an optional period like rsi14.",
+                    name
+                );
+                std::process::exit(cli::USAGE);
+            }
+        }
+    }
+    columns
+}
@@ -1,15 +1,22 @@
 #![forbid(unsafe_code)]
-#[allow(dead_code)]
-use std::io::{stdout, Write};
+
+use std::{
+    io::{stdout, Write},
+    sync::atomic::{AtomicBool, Ordering},
+};
 
 use crossterm::{
     cursor::{position, Hide, MoveTo, MoveToNextLine, Show},
-    style::{Color, SetForegroundColor},
+    style::{Attribute, Color, SetAttribute, SetBackgroundColor, SetForegroundColor},
     terminal, ExecutableCommand,
 };
 
+///Tracks whether Alternate Screen is active so cleanup only reverts what setup changed
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+
 ///Sets up terminal Alternate Screen
 pub fn setup() {
+    ACTIVE.store(true, Ordering::SeqCst);
     stdout()
         .execute(terminal::EnterAlternateScreen)
         .expect("Terminal Error");
@@ -24,17 +31,13 @@ pub fn setup() {
 
 ///Writes text to the screen
 pub fn write(text: &str) {
-    stdout()
-        .write(text.to_string().as_bytes())
-        .expect("Terminal Error");
+    stdout().write_all(text.as_bytes()).expect("Terminal Error");
     stdout().flush().expect("Terminal error.");
 }
 
 ///Writes text to the screen then moves cursor to next line
 pub fn write_then_nextline(text: &str) {
-    stdout()
-        .write(text.to_string().as_bytes())
-        .expect("Terminal Error");
+    stdout().write_all(text.as_bytes()).expect("Terminal Error");
     stdout()
         .execute(MoveToNextLine(1))
         .expect("Terminal error.");
@@ -43,13 +46,11 @@ pub fn write_then_nextline(text: &str) {
 
 ///Writes text to the screen and makes sure it occupies the entire space provided
 pub fn write_within_space(text: &str, space: u16) {
-    stdout()
-        .write(text.to_string().as_bytes())
-        .expect("Terminal Error");
+    stdout().write_all(text.as_bytes()).expect("Terminal Error");
     let (column, _) = position().expect("Terminal error.");
-    let blanks = space - column;
+    let blanks = space.saturating_sub(column);
     for _ in 0..blanks {
-        stdout().write(" ".as_bytes()).expect("Terminal error.");
+        stdout().write_all(" ".as_bytes()).expect("Terminal error.");
     }
     stdout().flush().expect("Terminal error.");
 }
@@ -70,12 +71,14 @@ pub fn skip_line() {
     stdout().flush().expect("Terminal error.");
 }
 
-///Sets text color
-pub fn set_color(color: Color) {
-    stdout()
-        .execute(SetForegroundColor(color))
-        .expect("Terminal error.");
-    stdout().flush().expect("Terminal error.");
+///Sets text color, keeps the terminal's default color if none is provided
+pub fn set_color(color: Option<Color>) {
+    if let Some(color) = color {
+        stdout()
+            .execute(SetForegroundColor(color))
+            .expect("Terminal error.");
+        stdout().flush().expect("Terminal error.");
+    }
 }
 
 ///Resets text color
@@ -86,6 +89,59 @@ pub fn reset_color() {
     stdout().flush().expect("Terminal error.");
 }
 
+///Sets background color, keeps the terminal's default background if none is provided
+pub fn set_background(color: Option<Color>) {
+    if let Some(color) = color {
+        stdout()
+            .execute(SetBackgroundColor(color))
+            .expect("Terminal error.");
+        stdout().flush().expect("Terminal error.");
+    }
+}
+
+///Resets background color
+pub fn reset_background() {
+    stdout()
+        .execute(SetBackgroundColor(Color::Reset))
+        .expect("Terminal error.");
+    stdout().flush().expect("Terminal error.");
+}
+
+///Sets text attribute like bold or dim
+pub fn set_attribute(attribute: Attribute) {
+    stdout()
+        .execute(SetAttribute(attribute))
+        .expect("Terminal error.");
+    stdout().flush().expect("Terminal error.");
+}
+
+///Resets text attributes back to normal intensity
+pub fn reset_attribute() {
+    stdout()
+        .execute(SetAttribute(Attribute::NormalIntensity))
+        .expect("Terminal error.");
+    stdout().flush().expect("Terminal error.");
+}
+
+///Moves cursor to provided column and row
+pub fn move_to(column: u16, row: u16) {
+    stdout()
+        .execute(MoveTo(column, row))
+        .expect("Terminal error.");
+}
+
+///Clears the whole screen
+pub fn clear() {
+    stdout()
+        .execute(terminal::Clear(terminal::ClearType::All))
+        .expect("Terminal error.");
+}
+
+///Returns terminal size as columns and rows
+pub fn size() -> (u16, u16) {
+    terminal::size().expect("Terminal error.")
+}
+
 ///Moves cursor to top left
 pub fn reset_cursor() {
     stdout().execute(MoveTo(0, 0)).expect("Terminal error.");
@@ -93,6 +149,9 @@ pub fn reset_cursor() {
 
 ///Reverts screen from Alternate Screen
 pub fn cleanup() {
+    if !ACTIVE.swap(false, Ordering::SeqCst) {
+        return;
+    }
     terminal::disable_raw_mode().unwrap();
     stdout().execute(terminal::LeaveAlternateScreen).unwrap();
     stdout().execute(Show).unwrap();
@@ -0,0 +1,251 @@
+#![forbid(unsafe_code)]
+
+use std::time::{Duration, Instant};
+
+use crossterm::{
+    event::{poll, read, Event, KeyCode, KeyEvent, KeyModifiers},
+    style::Attribute,
+};
+use yahoo_finance_api::YQuoteItem;
+
+use crate::{config, filter, output, palette, yahoo};
+
+///Time to wait after the last key press before searching again
+const DEBOUNCE: Duration = Duration::from_millis(400);
+
+///Search screen where results can be picked and added to a watchlist
+struct Picker {
+    query: String,
+    results: Vec<YQuoteItem>,
+    cursor: usize,
+    selected: Vec<String>,
+    ///Watchlist name being typed, None while browsing results
+    prompt: Option<String>,
+    message: Option<String>,
+    failed: bool,
+}
+
+///Runs search screen, searching again as the query is typed
+pub fn run(query: &str, kind: Option<&str>, exchange: Option<&str>) {
+    output::setup();
+    let search =
+        |query: &str| yahoo::try_search(query).map(|result| filter(result.quotes, kind, exchange));
+    let mut picker = Picker {
+        query: query.to_string(),
+        results: vec![],
+        cursor: 0,
+        selected: vec![],
+        prompt: None,
+        message: None,
+        failed: false,
+    };
+    //Searches once due, typing pushes it back so only the final query is requested
+    let mut due = Some(Instant::now());
+    loop {
+        if due.is_some_and(|due| Instant::now() >= due) {
+            due = None;
+            match search(&picker.query) {
+                Some(results) => {
+                    picker.results = results;
+                    picker.failed = false;
+                }
+                None => {
+                    picker.results = vec![];
+                    picker.failed = !picker.query.trim().is_empty();
+                }
+            }
+            picker.cursor = 0;
+        }
+        picker.draw();
+        let wait = due.map_or(Duration::from_secs(60), |due| {
+            due.saturating_duration_since(Instant::now())
+        });
+        if !poll(wait).expect("Terminal error.") {
+            continue;
+        }
+        let key = match read().expect("Terminal error.") {
+            Event::Key(key) => key,
+            _ => continue,
+        };
+        if key.code == KeyCode::Esc && picker.prompt.is_none()
+            || key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL)
+        {
+            break;
+        }
+        picker.message = None;
+        if picker.prompt.is_some() {
+            picker.prompt_key(key);
+        } else if picker.browse_key(key) {
+            due = Some(Instant::now() + DEBOUNCE);
+        }
+    }
+}
+
+impl Picker {
+    ///Handles key while browsing, returning whether the query changed
+    fn browse_key(&mut self, key: KeyEvent) -> bool {
+        match key.code {
+            KeyCode::Up => self.cursor = self.cursor.saturating_sub(1),
+            KeyCode::Down => {
+                self.cursor = (self.cursor + 1).min(self.results.len().saturating_sub(1))
+            }
+            KeyCode::Char(' ') => {
+                if let Some(item) = self.results.get(self.cursor) {
+                    match self
+                        .selected
+                        .iter()
+                        .position(|symbol| *symbol == item.symbol)
+                    {
+                        Some(index) => {
+                            self.selected.remove(index);
+                        }
+                        None => self.selected.push(item.symbol.clone()),
+                    }
+                }
+            }
+            KeyCode::Enter => {
+                if self.selected.is_empty() {
+                    if let Some(item) = self.results.get(self.cursor) {
+                        self.selected.push(item.symbol.clone());
+                    }
+                }
+                if !self.selected.is_empty() {
+                    self.prompt = Some(String::new());
+                }
+            }
+            KeyCode::Backspace => return self.query.pop().is_some(),
+            KeyCode::Char(c) => {
+                self.query.push(c);
+                return true;
+            }
+            _ => {}
+        }
+        false
+    }
+
+    ///Handles key while typing the watchlist name, Tab completes it from saved watchlists
+    fn prompt_key(&mut self, key: KeyEvent) {
+        let name = self.prompt.get_or_insert_with(String::new);
+        match key.code {
+            KeyCode::Esc => self.prompt = None,
+            KeyCode::Backspace => {
+                name.pop();
+            }
+            KeyCode::Tab => {
+                let matching = watchlists(name);
+                if let Some(first) = matching.first() {
+                    let mut common = first.clone();
+                    for other in &matching[1..] {
+                        while !other.starts_with(common.as_str()) {
+                            common.pop();
+                        }
+                    }
+                    *name = common;
+                }
+            }
+            KeyCode::Enter if !name.trim().is_empty() => {
+                let name = name.trim().to_string();
+                self.prompt = None;
+                self.add(&name);
+            }
+            KeyCode::Char(c) => name.push(c),
+            _ => {}
+        }
+    }
+
+    ///Adds selected tickers to a watchlist, creating it if needed
+    fn add(&mut self, watchlist: &str) {
+        let lock = config::lock();
+        let mut cfg = config::load();
+        let tickers = cfg.watchlists.entry(watchlist.to_string()).or_default();
+        let mut added = 0;
+        for symbol in self.selected.drain(..) {
+            if !tickers.contains(&symbol) {
+                tickers.push(symbol);
+                added += 1;
+            }
+        }
+        config::store(&cfg);
+        drop(lock);
+        self.message = Some(format!(
+            "{} ticker/s added to \"{}\". Use undo to take them back.",
+            added, watchlist
+        ));
+    }
+
+    ///Draws whole screen
+    fn draw(&self) {
+        let (width, height) = output::size();
+        //Some terminals report no size, lines are left whole then
+        let width = if width == 0 {
+            usize::MAX
+        } else {
+            width as usize
+        };
+        let line = |text: &str| {
+            let text: String = text.chars().take(width).collect();
+            output::write_then_nextline(&text);
+        };
+        output::clear();
+        output::reset_cursor();
+        line(&format!("Search: {}", self.query));
+        output::skip_line();
+        //Keeps rows for the search line and the footer
+        let rows = (height as usize).saturating_sub(7).max(1);
+        let offset = self.cursor.saturating_sub(rows - 1);
+        if self.failed {
+            output::set_color(palette(&config::load()).error);
+            line("Search failed. Keep typing to try again.");
+            output::reset_color();
+        } else if self.results.is_empty() {
+            line("No results.");
+        }
+        for (index, item) in self.results.iter().enumerate().skip(offset).take(rows) {
+            let mark = if self.selected.contains(&item.symbol) {
+                "[x]"
+            } else {
+                "[ ]"
+            };
+            if index == self.cursor {
+                output::set_attribute(Attribute::Reverse);
+            }
+            line(&format!(
+                "{} {:<14}{:<10}{:<16}{}",
+                mark, item.symbol, item.exchange, item.type_display, item.short_name
+            ));
+            output::set_attribute(Attribute::NoReverse);
+        }
+        output::skip_line();
+        if let Some(name) = &self.prompt {
+            line(&format!(
+                "Add {} ticker/s to watchlist: {}",
+                self.selected.len(),
+                name
+            ));
+            let matching = watchlists(name);
+            if !matching.is_empty() {
+                line(&format!("Tab completes: {}", matching.join(", ")));
+            }
+            line("Enter to add, Esc to go back.");
+        } else {
+            if let Some(message) = &self.message {
+                line(message);
+            }
+            line(&format!(
+                "{} selected. Type to search, Up/Down to move, Space to select, Enter to add to a watchlist, Esc to quit.",
+                self.selected.len()
+            ));
+        }
+    }
+}
+
+///Returns saved watchlists starting with the provided text
+fn watchlists(prefix: &str) -> Vec<String> {
+    let mut names: Vec<String> = config::load()
+        .watchlists
+        .into_keys()
+        .filter(|name| name.starts_with(prefix))
+        .collect();
+    names.sort();
+    names
+}
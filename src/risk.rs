@@ -0,0 +1,162 @@
+#![forbid(unsafe_code)]
+
+///Returns between consecutive values, as fractions
+pub fn returns(values: &[f64]) -> Vec<f64> {
+    values
+        .windows(2)
+        .map(|pair| pair[1] / pair[0] - 1.0)
+        .collect()
+}
+
+///Average number of bars in a year, from the time the bars span
+pub fn bars_per_year(timestamps: &[u64]) -> Option<f64> {
+    let (first, last) = (*timestamps.first()?, *timestamps.last()?);
+    let years = last.checked_sub(first)? as f64 / (365.25 * 24.0 * 3600.0);
+    (years > 0.0).then(|| (timestamps.len() - 1) as f64 / years)
+}
+
+///Mean of values
+pub fn mean(values: &[f64]) -> Option<f64> {
+    (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64)
+}
+
+///Sample standard deviation of values
+pub fn deviation(values: &[f64]) -> Option<f64> {
+    let mean = mean(values)?;
+    (values.len() > 1).then(|| {
+        (values
+            .iter()
+            .map(|value| (value - mean).powi(2))
+            .sum::<f64>()
+            / (values.len() - 1) as f64)
+            .sqrt()
+    })
+}
+
+///Annualized standard deviation of returns, in percent
+pub fn volatility(returns: &[f64], per_year: f64) -> Option<f64> {
+    deviation(returns).map(|deviation| deviation * per_year.sqrt() * 100.0)
+}
+
+///Largest fall from a previous peak, in percent, zero or negative
+pub fn max_drawdown(values: &[f64]) -> f64 {
+    let mut peak = f64::MIN;
+    let mut drawdown: f64 = 0.0;
+    for value in values {
+        peak = peak.max(*value);
+        drawdown = drawdown.min((value / peak - 1.0) * 100.0);
+    }
+    drawdown
+}
+
+///Sample covariance of two series of the same length
+pub fn covariance(first: &[f64], second: &[f64]) -> Option<f64> {
+    let (first_mean, second_mean) = (mean(first)?, mean(second)?);
+    (first.len() == second.len() && first.len() > 1).then(|| {
+        first
+            .iter()
+            .zip(second)
+            .map(|(a, b)| (a - first_mean) * (b - second_mean))
+            .sum::<f64>()
+            / (first.len() - 1) as f64
+    })
+}
+
+///Pearson correlation of two series of the same length, from -1 to 1
+pub fn correlation(first: &[f64], second: &[f64]) -> Option<f64> {
+    let deviations = deviation(first)? * deviation(second)?;
+    (deviations > 0.0).then_some(covariance(first, second)? / deviations)
+}
+
+///How much returns move with the benchmark's, 1 moves as much, 0 not at all
+pub fn beta(returns: &[f64], benchmark: &[f64]) -> Option<f64> {
+    let variance = deviation(benchmark)?.powi(2);
+    (variance > 0.0).then_some(covariance(returns, benchmark)? / variance)
+}
+
+///Annualized return above the risk-free rate in percent, per unit of annualized volatility
+pub fn sharpe(returns: &[f64], per_year: f64, risk_free: f64) -> Option<f64> {
+    let excess = mean(returns)? * per_year - risk_free / 100.0;
+    let volatility = deviation(returns)? * per_year.sqrt();
+    (volatility > 0.0).then_some(excess / volatility)
+}
+
+///Keeps values of days both daily series have, as series of trading on different calendars do not line up
+///
+///Days are cut at midnight of the times given, so times should be local to each exchange
+pub fn align(first: &[(u64, f64)], second: &[(u64, f64)]) -> (Vec<f64>, Vec<f64>) {
+    let day = |time: u64| time / (24 * 3600);
+    let days: std::collections::HashMap<u64, f64> = second
+        .iter()
+        .map(|(time, value)| (day(*time), *value))
+        .collect();
+    first
+        .iter()
+        .filter_map(|(time, value)| Some((*value, *days.get(&day(*time))?)))
+        .unzip()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: Option<f64>, expected: f64) {
+        let actual = actual.expect("No value");
+        assert!(
+            (actual - expected).abs() < 0.0005,
+            "{} is not {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn volatility_annualizes_sample_deviation() {
+        let returns = returns(&[100.0, 110.0, 99.0]);
+        assert_close(deviation(&returns), 0.141421);
+        assert_close(volatility(&returns, 252.0), 224.499);
+    }
+
+    #[test]
+    fn sharpe_takes_risk_free_rate_in_percent() {
+        let returns = [0.01, 0.03];
+        assert_close(sharpe(&returns, 4.0, 2.0), 2.12132);
+        assert_close(sharpe(&returns, 4.0, 0.0), 2.82843);
+        assert_eq!(sharpe(&[0.01, 0.01], 4.0, 2.0), None);
+    }
+
+    #[test]
+    fn beta_measures_moves_against_benchmark() {
+        let benchmark = [0.01, -0.02, 0.03, 0.005];
+        let doubled: Vec<f64> = benchmark.iter().map(|value| value * 2.0).collect();
+        assert_close(beta(&doubled, &benchmark), 2.0);
+        assert_close(beta(&[0.02, -0.01, 0.0, 0.01], &benchmark), 0.256158);
+        assert_eq!(beta(&doubled, &[0.01; 4]), None);
+    }
+
+    #[test]
+    fn correlation_matches_pearson() {
+        let first = [1.0, 2.0, 3.0, 4.0, 5.0];
+        assert_close(correlation(&first, &[2.0, 4.0, 5.0, 4.0, 5.0]), 0.774597);
+        assert_close(correlation(&first, &[5.0, 4.0, 3.0, 2.0, 1.0]), -1.0);
+        assert_eq!(correlation(&first, &[1.0; 5]), None);
+    }
+
+    #[test]
+    fn max_drawdown_takes_largest_fall_from_a_peak() {
+        assert_eq!(max_drawdown(&[100.0, 120.0, 90.0, 130.0, 117.0]), -25.0);
+        assert_eq!(max_drawdown(&[1.0, 2.0, 3.0]), 0.0);
+    }
+
+    #[test]
+    fn align_keeps_days_both_series_have() {
+        let day = 24 * 3600;
+        let first = [(10 * 3600, 1.0), (day + 10 * 3600, 2.0), (2 * day, 3.0)];
+        let second = [
+            (day + 20 * 3600, 5.0),
+            (2 * day + 3600, 6.0),
+            (3 * day, 7.0),
+        ];
+        assert_eq!(align(&first, &second), (vec![2.0, 3.0], vec![5.0, 6.0]));
+    }
+}
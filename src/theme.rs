@@ -0,0 +1,315 @@
+#![forbid(unsafe_code)]
+
+use std::convert::TryFrom;
+
+use crossterm::style::Color;
+use serde::{Deserialize, Serialize};
+
+///Names of themes shipped with zigfi
+pub const BUILTIN: [&str; 4] = ["default", "east-asian", "high-contrast", "monochrome"];
+
+///Keys a theme table knows about
+pub const KEYS: [&str; 7] = [
+    "up",
+    "down",
+    "unchanged",
+    "price",
+    "header",
+    "stale",
+    "error",
+];
+
+///Theme as written in the configuration, colors are names ("dark_green"), ANSI values ("208") or hex ("#ff8800")
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct Theme {
+    pub up: String,
+    pub down: String,
+    pub unchanged: String,
+    pub price: String,
+    pub header: String,
+    pub stale: String,
+    pub error: String,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            up: "green".to_string(),
+            down: "red".to_string(),
+            unchanged: "grey".to_string(),
+            price: "yellow".to_string(),
+            header: "cyan".to_string(),
+            stale: "dark_grey".to_string(),
+            error: "red".to_string(),
+        }
+    }
+}
+
+impl Theme {
+    ///Returns built-in theme with the provided name
+    pub fn builtin(name: &str) -> Option<Theme> {
+        match name {
+            "default" => Some(Theme::default()),
+            "east-asian" => Some(Theme::default().swapped()),
+            "high-contrast" => Some(Theme {
+                up: "#00ff00".to_string(),
+                down: "#ff0000".to_string(),
+                unchanged: "white".to_string(),
+                price: "#ffff00".to_string(),
+                header: "white".to_string(),
+                stale: "grey".to_string(),
+                error: "#ff0000".to_string(),
+            }),
+            "monochrome" => Some(Theme {
+                up: "default".to_string(),
+                down: "default".to_string(),
+                unchanged: "default".to_string(),
+                price: "default".to_string(),
+                header: "default".to_string(),
+                stale: "default".to_string(),
+                error: "default".to_string(),
+            }),
+            _ => None,
+        }
+    }
+
+    ///Swaps colors for up and down moves
+    pub fn swapped(mut self) -> Theme {
+        std::mem::swap(&mut self.up, &mut self.down);
+        self
+    }
+}
+
+///Number of colors the terminal can show
+#[derive(Clone, Copy, PartialEq, PartialOrd)]
+pub enum Depth {
+    NoColor,
+    Basic,
+    Ansi256,
+    TrueColor,
+}
+
+impl Depth {
+    ///Detects color support from NO_COLOR, COLORTERM and TERM
+    pub fn detect() -> Depth {
+        if std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty()) {
+            return Depth::NoColor;
+        }
+        let colorterm = std::env::var("COLORTERM").unwrap_or_default();
+        if colorterm.eq_ignore_ascii_case("truecolor") || colorterm.eq_ignore_ascii_case("24bit") {
+            return Depth::TrueColor;
+        }
+        let term = std::env::var("TERM").unwrap_or_default();
+        if term == "dumb" {
+            Depth::NoColor
+        } else if term.contains("256color") {
+            Depth::Ansi256
+        } else {
+            Depth::Basic
+        }
+    }
+}
+
+///Theme resolved into terminal colors, None leaves the terminal's default color
+pub struct Palette {
+    pub up: Option<Color>,
+    pub down: Option<Color>,
+    pub unchanged: Option<Color>,
+    pub price: Option<Color>,
+    pub header: Option<Color>,
+    pub stale: Option<Color>,
+    pub error: Option<Color>,
+    ///Colors of up, down and unchanged moves as RGB before fitting, used to blend gradients
+    up_rgb: Option<(u8, u8, u8)>,
+    down_rgb: Option<(u8, u8, u8)>,
+    unchanged_rgb: Option<(u8, u8, u8)>,
+    depth: Depth,
+}
+
+impl Palette {
+    ///Resolves theme colors for the provided color depth
+    pub fn new(theme: &Theme, depth: Depth) -> Result<Palette, String> {
+        let parsed = |key: &str, value: &str| {
+            parse(value).ok_or(format!(
+                "Invalid color \"{}\" for \"{}\" in theme.",
+                value, key
+            ))
+        };
+        let resolve = |key: &str, value: &str| parsed(key, value).map(|color| fit(color, depth));
+        Ok(Palette {
+            up: resolve("up", &theme.up)?,
+            down: resolve("down", &theme.down)?,
+            unchanged: resolve("unchanged", &theme.unchanged)?,
+            price: resolve("price", &theme.price)?,
+            header: resolve("header", &theme.header)?,
+            stale: resolve("stale", &theme.stale)?,
+            error: resolve("error", &theme.error)?,
+            up_rgb: to_rgb(parsed("up", &theme.up)?),
+            down_rgb: to_rgb(parsed("down", &theme.down)?),
+            unchanged_rgb: to_rgb(parsed("unchanged", &theme.unchanged)?),
+            depth,
+        })
+    }
+
+    ///Returns color for the nth line of a chart
+    pub fn series(&self, index: usize) -> Option<Color> {
+        const SERIES: [Color; 6] = [
+            Color::Cyan,
+            Color::Yellow,
+            Color::Magenta,
+            Color::Green,
+            Color::Blue,
+            Color::Red,
+        ];
+        fit(SERIES[index % SERIES.len()], self.depth)
+    }
+
+    ///Returns color for a price move
+    pub fn change(&self, difference: f64) -> Option<Color> {
+        if difference == 0.0 {
+            self.unchanged
+        } else if difference.is_sign_positive() {
+            self.up
+        } else {
+            self.down
+        }
+    }
+
+    ///Returns color for a price move, blended from the unchanged color toward the up or down color as the move nears range percent
+    pub fn gradient(&self, difference: f64, range: f64) -> Option<Color> {
+        let target = if difference > 0.0 {
+            self.up_rgb
+        } else if difference < 0.0 {
+            self.down_rgb
+        } else {
+            return self.unchanged;
+        };
+        let (from, to) = match (self.unchanged_rgb, target) {
+            (Some(from), Some(to)) => (from, to),
+            _ => return self.change(difference),
+        };
+        //Starts partway so even the smallest move shows its direction
+        let strength = 0.35 + 0.65 * (difference.abs() / range).clamp(0.0, 1.0);
+        let mix = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * strength).round() as u8;
+        fit(
+            Color::Rgb {
+                r: mix(from.0, to.0),
+                g: mix(from.1, to.1),
+                b: mix(from.2, to.2),
+            },
+            self.depth,
+        )
+    }
+}
+
+///Parses color name, ANSI value or hex code
+fn parse(value: &str) -> Option<Color> {
+    let value = value.trim();
+    if value.eq_ignore_ascii_case("default") || value.eq_ignore_ascii_case("reset") {
+        return Some(Color::Reset);
+    }
+    if let Some(hex) = value.strip_prefix('#') {
+        if hex.len() != 6 || !hex.is_ascii() {
+            return None;
+        }
+        let channel = |index: usize| u8::from_str_radix(&hex[index..index + 2], 16).ok();
+        return Some(Color::Rgb {
+            r: channel(0)?,
+            g: channel(2)?,
+            b: channel(4)?,
+        });
+    }
+    if let Ok(ansi) = value.parse::<u8>() {
+        return Some(Color::AnsiValue(ansi));
+    }
+    Color::try_from(value).ok()
+}
+
+///Downgrades color to what the terminal supports
+fn fit(color: Color, depth: Depth) -> Option<Color> {
+    match (color, depth) {
+        (_, Depth::NoColor) | (Color::Reset, _) => None,
+        (Color::Rgb { r, g, b }, Depth::Ansi256) => Some(Color::AnsiValue(rgb_to_ansi(r, g, b))),
+        (Color::Rgb { r, g, b }, Depth::Basic) => Some(nearest_basic((r, g, b))),
+        (Color::AnsiValue(ansi), Depth::Basic) => Some(nearest_basic(ansi_to_rgb(ansi))),
+        (color, _) => Some(color),
+    }
+}
+
+///Returns RGB value of a color, None for the terminal's default color
+fn to_rgb(color: Color) -> Option<(u8, u8, u8)> {
+    match color {
+        Color::Reset => None,
+        Color::Rgb { r, g, b } => Some((r, g, b)),
+        Color::AnsiValue(ansi) => Some(ansi_to_rgb(ansi)),
+        named => BASIC
+            .iter()
+            .find(|(color, _)| *color == named)
+            .map(|(_, rgb)| *rgb),
+    }
+}
+
+///Standard 16 terminal colors and their approximate RGB values
+const BASIC: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::DarkRed, (128, 0, 0)),
+    (Color::DarkGreen, (0, 128, 0)),
+    (Color::DarkYellow, (128, 128, 0)),
+    (Color::DarkBlue, (0, 0, 128)),
+    (Color::DarkMagenta, (128, 0, 128)),
+    (Color::DarkCyan, (0, 128, 128)),
+    (Color::Grey, (192, 192, 192)),
+    (Color::DarkGrey, (128, 128, 128)),
+    (Color::Red, (255, 0, 0)),
+    (Color::Green, (0, 255, 0)),
+    (Color::Yellow, (255, 255, 0)),
+    (Color::Blue, (0, 0, 255)),
+    (Color::Magenta, (255, 0, 255)),
+    (Color::Cyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+///Converts RGB to the closest color of the 256 color cube or grayscale ramp
+fn rgb_to_ansi(r: u8, g: u8, b: u8) -> u8 {
+    let level = |channel: u8| ((channel as u16 * 5 + 127) / 255) as u8;
+    if r == g && g == b {
+        if r < 8 {
+            return 16;
+        }
+        if r > 248 {
+            return 231;
+        }
+        return 232 + ((r as u16 - 8) * 24 / 247) as u8;
+    }
+    16 + 36 * level(r) + 6 * level(g) + level(b)
+}
+
+///Converts ANSI 256 color value to RGB
+fn ansi_to_rgb(ansi: u8) -> (u8, u8, u8) {
+    match ansi {
+        0..=15 => BASIC[ansi as usize].1,
+        16..=231 => {
+            let index = ansi - 16;
+            let scale = |level: u8| if level == 0 { 0 } else { 55 + level * 40 };
+            (scale(index / 36), scale(index / 6 % 6), scale(index % 6))
+        }
+        _ => {
+            let gray = 8 + (ansi - 232) * 10;
+            (gray, gray, gray)
+        }
+    }
+}
+
+///Finds closest of the 16 standard terminal colors
+fn nearest_basic(rgb: (u8, u8, u8)) -> Color {
+    let distance = |other: (u8, u8, u8)| {
+        let delta = |a: u8, b: u8| (a as i32 - b as i32).pow(2);
+        delta(rgb.0, other.0) + delta(rgb.1, other.1) + delta(rgb.2, other.2)
+    };
+    BASIC
+        .iter()
+        .min_by_key(|(_, other)| distance(*other))
+        .map(|(color, _)| *color)
+        .unwrap_or(Color::Reset)
+}
@@ -0,0 +1,279 @@
+#![forbid(unsafe_code)]
+
+use std::{collections::BTreeMap, path::Path};
+
+///File formats watchlists can be exported to and imported from
+#[derive(Clone, Copy, PartialEq)]
+pub enum Format {
+    Json,
+    Toml,
+    Csv,
+    Txt,
+}
+
+impl Format {
+    ///Returns format for names used on the command line
+    pub fn from_name(name: &str) -> Option<Format> {
+        match name.to_ascii_lowercase().as_str() {
+            "json" => Some(Format::Json),
+            "toml" => Some(Format::Toml),
+            "csv" => Some(Format::Csv),
+            "txt" | "text" => Some(Format::Txt),
+            _ => None,
+        }
+    }
+
+    ///Guesses format from a file extension
+    pub fn from_path(path: &Path) -> Option<Format> {
+        Format::from_name(path.extension()?.to_str()?)
+    }
+}
+
+///Watchlists in the order they are written, each with its tickers
+pub type Watchlists = Vec<(String, Vec<String>)>;
+
+///Writes watchlists in the provided format
+pub fn export(watchlists: &Watchlists, format: Format) -> String {
+    match format {
+        Format::Json | Format::Toml => {
+            let mut table = BTreeMap::new();
+            table.insert(
+                "watchlists",
+                watchlists.iter().cloned().collect::<BTreeMap<_, _>>(),
+            );
+            if format == Format::Json {
+                serde_json::to_string_pretty(&table).expect("Failed to write JSON.") + "\n"
+            } else {
+                toml::to_string_pretty(&table).expect("Failed to write TOML.")
+            }
+        }
+        Format::Csv => {
+            let mut text = String::from("watchlist,ticker\n");
+            for (name, tickers) in watchlists {
+                //Empty watchlists get a row without a ticker so they survive the trip
+                if tickers.is_empty() {
+                    text.push_str(&format!("{},\n", csv_field(name)));
+                }
+                for ticker in tickers {
+                    text.push_str(&format!("{},{}\n", csv_field(name), csv_field(ticker)));
+                }
+            }
+            text
+        }
+        Format::Txt => {
+            let mut sections = vec![];
+            for (name, tickers) in watchlists {
+                let mut section = format!("[{}]\n", name);
+                for ticker in tickers {
+                    section.push_str(&format!("{}\n", ticker));
+                }
+                sections.push(section);
+            }
+            sections.join("\n")
+        }
+    }
+}
+
+///Reads watchlists written in the provided format
+pub fn import(text: &str, format: Format) -> Result<Watchlists, String> {
+    match format {
+        Format::Json | Format::Toml => {
+            #[derive(serde::Deserialize)]
+            struct File {
+                watchlists: BTreeMap<String, Vec<String>>,
+            }
+            let file: File = if format == Format::Json {
+                serde_json::from_str(text).map_err(|error| format!("Invalid JSON: {}", error))?
+            } else {
+                toml::from_str(text).map_err(|error| format!("Invalid TOML: {}", error))?
+            };
+            Ok(file.watchlists.into_iter().collect())
+        }
+        Format::Csv => {
+            let mut watchlists: Watchlists = vec![];
+            for (index, line) in text.lines().enumerate() {
+                if line.trim().is_empty() || (index == 0 && line.trim() == "watchlist,ticker") {
+                    continue;
+                }
+                let fields = csv_split(line);
+                if fields.len() != 2 {
+                    return Err(format!(
+                        "Line {} should have a watchlist and a ticker.",
+                        index + 1
+                    ));
+                }
+                if fields[1].is_empty() {
+                    if !watchlists
+                        .iter()
+                        .any(|(existing, _)| existing == &fields[0])
+                    {
+                        watchlists.push((fields[0].clone(), vec![]));
+                    }
+                } else {
+                    push(&mut watchlists, &fields[0], &fields[1]);
+                }
+            }
+            Ok(watchlists)
+        }
+        Format::Txt => {
+            let mut watchlists: Watchlists = vec![];
+            let mut current: Option<String> = None;
+            for (index, line) in text.lines().enumerate() {
+                let line = line.trim();
+                if line.is_empty() {
+                    continue;
+                }
+                if let Some(name) = line
+                    .strip_prefix('[')
+                    .and_then(|rest| rest.strip_suffix(']'))
+                {
+                    if !watchlists.iter().any(|(existing, _)| existing == name) {
+                        watchlists.push((name.to_string(), vec![]));
+                    }
+                    current = Some(name.to_string());
+                    continue;
+                }
+                match &current {
+                    Some(name) => push(&mut watchlists, name, line),
+                    None => {
+                        return Err(format!(
+                            "Line {} has a ticker before any [watchlist] heading.",
+                            index + 1
+                        ))
+                    }
+                }
+            }
+            Ok(watchlists)
+        }
+    }
+}
+
+///Reads tickers separated by newlines or commas, skipping blank and # comment lines
+pub fn tickers(text: &str) -> Vec<String> {
+    text.lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.starts_with('#'))
+        .flat_map(|line| line.split(','))
+        .map(|ticker| ticker.trim())
+        .filter(|ticker| !ticker.is_empty())
+        .map(|ticker| ticker.to_string())
+        .collect()
+}
+
+///Adds ticker to a watchlist, creating the watchlist if it is new
+fn push(watchlists: &mut Watchlists, name: &str, ticker: &str) {
+    match watchlists.iter_mut().find(|(existing, _)| existing == name) {
+        Some((_, tickers)) => tickers.push(ticker.to_string()),
+        None => watchlists.push((name.to_string(), vec![ticker.to_string()])),
+    }
+}
+
+///Quotes CSV field if it has a comma or quote
+pub fn csv_field(text: &str) -> String {
+    if text.contains(',') || text.contains('"') {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+///Splits CSV line into fields, honoring quotes
+fn csv_split(line: &str) -> Vec<String> {
+    let mut fields = vec![];
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field).trim().to_string()),
+            _ => field.push(c),
+        }
+    }
+    fields.push(field.trim().to_string());
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Watchlists {
+        vec![
+            (
+                "crypto".to_string(),
+                vec!["BTC-USD".to_string(), "ETH-USD".to_string()],
+            ),
+            ("empty".to_string(), vec![]),
+            (
+                "metals, \"spot\"".to_string(),
+                vec!["GC=F".to_string(), "SI=F".to_string()],
+            ),
+        ]
+    }
+
+    #[test]
+    fn every_format_round_trips() {
+        for format in [Format::Json, Format::Toml, Format::Csv, Format::Txt] {
+            let text = export(&sample(), format);
+            assert_eq!(
+                import(&text, format).expect("Export not importable"),
+                sample()
+            );
+        }
+    }
+
+    #[test]
+    fn csv_quotes_fields_with_commas_and_quotes() {
+        assert_eq!(csv_field("GC=F"), "GC=F");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_split("\"a,b\", GC=F"), vec!["a,b", "GC=F"]);
+        assert_eq!(csv_split("\"say \"\"hi\"\"\",x"), vec!["say \"hi\"", "x"]);
+    }
+
+    #[test]
+    fn csv_keeps_empty_watchlists() {
+        let text = export(&vec![("empty".to_string(), vec![])], Format::Csv);
+        assert_eq!(text, "watchlist,ticker\nempty,\n");
+        assert_eq!(
+            import("empty,\nempty,AAPL\n", Format::Csv).expect("Rejected"),
+            vec![("empty".to_string(), vec!["AAPL".to_string()])]
+        );
+    }
+
+    #[test]
+    fn csv_rejects_rows_without_two_fields() {
+        assert_eq!(
+            import("watchlist,ticker\ncrypto\n", Format::Csv),
+            Err("Line 2 should have a watchlist and a ticker.".to_string())
+        );
+    }
+
+    #[test]
+    fn txt_reads_headings_and_rejects_tickers_before_them() {
+        assert_eq!(
+            import("[crypto]\nBTC-USD\n\n[crypto]\nETH-USD\n", Format::Txt).expect("Rejected"),
+            vec![(
+                "crypto".to_string(),
+                vec!["BTC-USD".to_string(), "ETH-USD".to_string()]
+            )]
+        );
+        assert_eq!(
+            import("BTC-USD\n[crypto]\n", Format::Txt),
+            Err("Line 1 has a ticker before any [watchlist] heading.".to_string())
+        );
+    }
+
+    #[test]
+    fn tickers_skip_comments_and_blanks() {
+        assert_eq!(
+            tickers("# mine\nAAPL, MSFT\n\nGC=F\n"),
+            vec!["AAPL", "MSFT", "GC=F"]
+        );
+    }
+}
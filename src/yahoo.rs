@@ -1,14 +1,24 @@
 #![forbid(unsafe_code)]
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, TimeZone, Utc};
 use tokio::runtime::Runtime;
 use yahoo::YSearchResult;
 use yahoo_finance_api as yahoo;
 
 use crate::format;
 
+///Latest price of a ticker and its move over the requested interval
+pub struct Price {
+    pub quote: f64,
+    pub difference: f64,
+    ///Decimals Yahoo Finance suggests for the instrument
+    pub hint: usize,
+    ///Time of the latest trade
+    pub time: DateTime<Utc>,
+}
+
 ///Requests ticker's current and previous price based on provided interval
-pub fn get(query: &str, from: DateTime<Utc>, to: DateTime<Utc>) -> (f64, f64) {
+pub fn get(query: &str, from: DateTime<Utc>, to: DateTime<Utc>) -> Price {
     let rt = Runtime::new().expect("Failed to start Runtime");
     let yahoo = yahoo::YahooConnector::new();
     let response = rt
@@ -17,31 +27,99 @@ pub fn get(query: &str, from: DateTime<Utc>, to: DateTime<Utc>) -> (f64, f64) {
     let quote = response
         .quotes()
         .expect("Failed to process Yahoo Finance Response.");
-    let difference = format::reduc(format::prcnt(
-        quote
-            .last()
-            .expect("Failed to process Yahoo Finance Response.")
-            .close,
+    let last = quote
+        .last()
+        .expect("Failed to process Yahoo Finance Response.");
+    let difference = format::prcnt(
+        last.close,
         quote
-            .get(0)
+            .first()
             .expect("Failed to process Yahoo Finance Response.")
             .close,
-    ));
-    (
-        format::reduc(
-            quote
-                .last()
-                .expect("Failed to process Yahoo Finance Response.")
-                .close,
-        ),
+    );
+    let meta = response.chart.result.first().map(|result| &result.meta);
+    let hint = meta.map_or(2, |meta| meta.price_hint.max(0) as usize);
+    let time = meta.map_or(last.timestamp as i64, |meta| {
+        meta.regular_market_time as i64
+    });
+    Price {
+        quote: last.close,
         difference,
-    )
+        hint,
+        time: Utc
+            .timestamp_opt(time, 0)
+            .single()
+            .expect("Failed to process Yahoo Finance Response."),
+    }
+}
+
+///Every bar of a ticker's history and the decimals Yahoo Finance suggests for it
+pub struct History {
+    pub bars: Vec<yahoo::Quote>,
+    pub hint: usize,
+    ///Seconds the exchange's time is ahead of UTC
+    pub offset: i64,
+}
+
+///Requests ticker's open, high, low, close, adjusted close and volume for every bar in the range
+pub fn history(query: &str, bar: &str, range: &str) -> History {
+    let rt = Runtime::new().expect("Failed to start Runtime");
+    let yahoo = yahoo::YahooConnector::new();
+    let response = rt
+        .block_on(yahoo.get_quote_range(query, bar, range))
+        .expect("Yahoo Finance request failed. Invalid ticker?");
+    let bars = response
+        .quotes()
+        .expect("Failed to process Yahoo Finance Response.");
+    let hint = response
+        .chart
+        .result
+        .first()
+        .map_or(2, |result| result.meta.price_hint.max(0) as usize);
+    let offset = response
+        .chart
+        .result
+        .first()
+        .map_or(0, |result| result.meta.gmtoffset as i64);
+    History { bars, hint, offset }
+}
+
+///What Yahoo Finance answered when asked about a ticker
+pub enum Lookup {
+    Found,
+    Missing,
+    ///The request itself failed, so whether the ticker exists is unknown
+    Failed(yahoo::YahooError),
+}
+
+///Checks whether Yahoo Finance has quotes for the ticker
+pub fn exists(query: &str, from: DateTime<Utc>, to: DateTime<Utc>) -> Lookup {
+    let rt = Runtime::new().expect("Failed to start Runtime");
+    let yahoo = yahoo::YahooConnector::new();
+    match rt.block_on(yahoo.get_quote_history(query, from, to)) {
+        Ok(response) => match response.quotes() {
+            Ok(quotes) if !quotes.is_empty() => Lookup::Found,
+            _ => Lookup::Missing,
+        },
+        //Unknown tickers answer with 404, anything else says nothing about the ticker
+        Err(yahoo::YahooError::FetchFailed(status)) if status.contains("404") => Lookup::Missing,
+        Err(
+            error @ (yahoo::YahooError::FetchFailed(_)
+            | yahoo::YahooError::ConnectionFailed
+            | yahoo::YahooError::InvalidJson),
+        ) => Lookup::Failed(error),
+        Err(_) => Lookup::Missing,
+    }
 }
 
 ///Returns search results of provided query
 pub fn search(query: &str) -> YSearchResult {
+    try_search(query).expect("Failed to process Yahoo Finance Response.")
+}
+
+///Returns search results of provided query, None if the request failed
+pub fn try_search(query: &str) -> Option<YSearchResult> {
     let rt = Runtime::new().expect("Failed to start Runtime");
     let yahoo = yahoo::YahooConnector::new();
-    rt.block_on(yahoo.search_ticker(query))
-        .expect("Failed to process Yahoo Finance Response.")
+    rt.block_on(yahoo.search_ticker(query)).ok()
 }